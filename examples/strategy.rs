@@ -128,6 +128,7 @@ impl FileMarketDataProvider {
 }
 
 impl SimulatedTradingMarketDataProvider for FileMarketDataProvider {
+    #[allow(clippy::question_mark)]
     fn next_event(&mut self) -> Option<MarketDataEvent> {
         let quote = match self.events_buffer.get(self.idx) {
             Some(val) => val,
@@ -188,7 +189,8 @@ impl SampleStrategy {
                 symbol: quote.symbol.clone(),
                 quantity: 1.0,
                 side: Side::BUY,
-                creation_ts: quote.received_timestamp,
+                // stamped from the actions context clock on send
+                creation_ts: 0,
             };
             debug!("new order request: {:?}", &request);
             if let Err(err) = actions_context.send_order(request) {
@@ -217,11 +219,11 @@ impl<MS: MessageSender<SimpleMessage>> Actor<SimpleMessage, MS> for SampleStrate
                 OrderStatus::NEW => {
                     let request = CancelOrderRequest {
                         request_id: "".to_string(),
-                        client_order_id: msg.client_order_id.as_ref().unwrap().clone(),
-                        exchange_order_id: msg.exchange_order_id.as_ref().unwrap().clone(),
+                        client_order_id: msg.client_order_id.clone(),
+                        exchange_order_id: msg.exchange_order_id.clone(),
                         exchange: msg.exchange.clone(),
                         symbol: msg.symbol.clone(),
-                        creation_ts: msg.exchange_timestamp,
+                        creation_ts: 0,
                     };
                     debug!("new cancel request: {:?}", &request);
                     actions_context
@@ -264,6 +266,7 @@ impl MessageHandler<SimpleMessage, CrossbeamMessageSender<SimpleMessage>> for My
         warn!("received new message: {:?}", message)
     }
 
+    #[allow(clippy::needless_return)]
     fn get_topics(&self) -> Vec<Topic> {
         return vec![];
     }
@@ -1,18 +1,24 @@
+use crate::core::clock::{Clock, WallClock};
 use crate::core::gateway_router::{
-    CancelOrderRequest, ExchangeRequest, GatewayRouter, GatewayRouterError, NewOrderRequest,
+    AmendOrderRequest, CancelAllRequest, CancelOrderRequest, ExchangeRequest, GatewayRouter,
+    GatewayRouterError, NewOrderRequest,
 };
 use crate::core::message_bus::{CrossbeamMessageSender, Message, MessageSender, SimpleMessage};
-use crate::core::types::Exchange;
-use crossbeam_channel::Receiver;
+use crate::core::timer::{TimerId, TimerRequest};
+use crate::core::types::{Exchange, Latency, Timestamp};
+use crossbeam_channel::{unbounded, Receiver, Sender};
 use log::warn;
 use std::collections::HashMap;
 use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
 #[derive(Debug)]
 pub enum ActionError {
     GatewayRouterError(GatewayRouterError),
     SendMessageError(String),
     ActionNotSupported(String),
+    TimerError(String),
 }
 
 impl From<GatewayRouterError> for ActionError {
@@ -26,6 +32,11 @@ pub struct ActionsContext<M: Message, T: MessageSender<M>> {
     phantom: PhantomData<M>,
     gw_router: GatewayRouter,
     message_sender: Option<T>,
+    timer_sender: Sender<TimerRequest>,
+    timer_receiver: Receiver<TimerRequest>,
+    // shared by clones, so timer ids are unique across event loop and message bus
+    last_timer_id: Arc<AtomicU64>,
+    clock: Arc<dyn Clock>,
 }
 
 impl<M: Message, T: MessageSender<M>> ActionsContext<M, T> {
@@ -33,30 +44,127 @@ impl<M: Message, T: MessageSender<M>> ActionsContext<M, T> {
         self.gw_router.receivers()
     }
     pub fn new_with_sender(gw_router: GatewayRouter, message_sender: T) -> Self {
+        let (timer_sender, timer_receiver) = unbounded();
         Self {
             phantom: Default::default(),
             gw_router,
             message_sender: Some(message_sender),
+            timer_sender,
+            timer_receiver,
+            last_timer_id: Default::default(),
+            clock: Arc::new(WallClock::default()),
         }
     }
 
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn clock(&self) -> Arc<dyn Clock> {
+        self.clock.clone()
+    }
+
+    /// Current time: simulated time in sim environment and wall clock otherwise.
+    /// Engine sets sim clock only for sim environment runs, so `start_with_event_provider`
+    /// uses `WallClock` even if the event provider replays historical data
+    pub fn now(&self) -> Timestamp {
+        self.clock.now()
+    }
+
+    /// Timer requests are consumed by sim environment or by `TimerEventProvider` engine wraps
+    /// external event provider into, both deliver `Event::Timer`
+    pub fn timer_requests_receiver(&self) -> Receiver<TimerRequest> {
+        self.timer_receiver.clone()
+    }
+
+    // creation_ts of requests is always stamped from the clock, value set by caller is ignored
+
+    #[allow(unknown_lints, clippy::result_large_err)]
     pub fn send_exchange_request(
         &mut self,
-        request: ExchangeRequest,
+        mut request: ExchangeRequest,
     ) -> Result<(), GatewayRouterError> {
+        request.set_creation_ts(self.now());
         self.gw_router.send_request(request)
     }
 
-    pub fn send_order(&mut self, request: NewOrderRequest) -> Result<(), ActionError> {
+    #[allow(unknown_lints, clippy::result_large_err)]
+    pub fn send_order(&mut self, mut request: NewOrderRequest) -> Result<(), ActionError> {
+        request.creation_ts = self.now();
         self.gw_router.send_order(request)?;
         Ok(())
     }
 
-    pub fn cancel_order(&mut self, request: CancelOrderRequest) -> Result<(), ActionError> {
+    #[allow(unknown_lints, clippy::result_large_err)]
+    pub fn cancel_order(&mut self, mut request: CancelOrderRequest) -> Result<(), ActionError> {
+        request.creation_ts = self.now();
         self.gw_router.cancel_order(request)?;
         Ok(())
     }
 
+    #[allow(unknown_lints, clippy::result_large_err)]
+    pub fn cancel_all(&mut self, mut request: CancelAllRequest) -> Result<(), ActionError> {
+        request.creation_ts = self.now();
+        self.gw_router.cancel_all(request)?;
+        Ok(())
+    }
+
+    #[allow(unknown_lints, clippy::result_large_err)]
+    pub fn amend_order(&mut self, mut request: AmendOrderRequest) -> Result<(), ActionError> {
+        request.creation_ts = self.now();
+        self.gw_router.amend_order(request)?;
+        Ok(())
+    }
+
+    /// Schedules one-shot timer which fires at fire_ts
+    #[allow(unknown_lints, clippy::result_large_err)]
+    pub fn schedule_timer(&mut self, fire_ts: Timestamp) -> Result<TimerId, ActionError> {
+        self.send_schedule_timer_request(fire_ts, None)
+    }
+
+    /// Schedules timer which fires at first_fire_ts and then every interval
+    #[allow(unknown_lints, clippy::result_large_err)]
+    pub fn schedule_recurring_timer(
+        &mut self,
+        first_fire_ts: Timestamp,
+        interval: Latency,
+    ) -> Result<TimerId, ActionError> {
+        if interval == 0 {
+            return Err(ActionError::TimerError("interval must be positive".into()));
+        }
+        self.send_schedule_timer_request(first_fire_ts, Some(interval))
+    }
+
+    #[allow(unknown_lints, clippy::result_large_err)]
+    pub fn cancel_timer(&mut self, timer_id: TimerId) -> Result<(), ActionError> {
+        self.send_timer_request(TimerRequest::Cancel(timer_id))
+    }
+
+    #[allow(unknown_lints, clippy::result_large_err)]
+    fn send_schedule_timer_request(
+        &mut self,
+        fire_ts: Timestamp,
+        interval: Option<Latency>,
+    ) -> Result<TimerId, ActionError> {
+        let timer_id = self.last_timer_id.fetch_add(1, Ordering::SeqCst) + 1;
+        self.send_timer_request(TimerRequest::Schedule {
+            timer_id,
+            fire_ts,
+            interval,
+        })?;
+        Ok(timer_id)
+    }
+
+    #[allow(unknown_lints, clippy::result_large_err)]
+    fn send_timer_request(&mut self, request: TimerRequest) -> Result<(), ActionError> {
+        match self.timer_sender.send(request) {
+            Ok(_) => Ok(()),
+            Err(err) => Err(ActionError::TimerError(format!("{:?}", err))),
+        }
+    }
+
+    #[allow(unknown_lints, clippy::result_large_err)]
     pub fn send_message(&mut self, message: M) -> Result<(), ActionError> {
         warn!("send new message: {:?}", &message);
         match &mut self.message_sender {
@@ -73,10 +181,15 @@ impl<M: Message, T: MessageSender<M>> ActionsContext<M, T> {
 
 impl ActionsContext<SimpleMessage, CrossbeamMessageSender<SimpleMessage>> {
     pub fn new(gw_router: GatewayRouter) -> Self {
+        let (timer_sender, timer_receiver) = unbounded();
         Self {
             phantom: Default::default(),
             gw_router,
             message_sender: None,
+            timer_sender,
+            timer_receiver,
+            last_timer_id: Default::default(),
+            clock: Arc::new(WallClock::default()),
         }
     }
 }
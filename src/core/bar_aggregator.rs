@@ -0,0 +1,260 @@
+use super::event_loop::EventProvider;
+use super::events::Event;
+use super::market_data::{Bar, BarType, Trade};
+use super::types::{Exchange, Symbol, Timestamp};
+use std::collections::{HashMap, VecDeque};
+
+#[derive(Debug, Clone)]
+struct PartialBar {
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: f64,
+    notional: f64,
+    trade_count: u64,
+    start_timestamp: Timestamp,
+    // set for time bars only
+    end_timestamp: Option<Timestamp>,
+    last_exchange_timestamp: Timestamp,
+}
+
+impl PartialBar {
+    fn new(trade: &Trade, start_timestamp: Timestamp, end_timestamp: Option<Timestamp>) -> Self {
+        Self {
+            open: trade.last_price,
+            high: trade.last_price,
+            low: trade.last_price,
+            close: trade.last_price,
+            volume: 0.0,
+            notional: 0.0,
+            trade_count: 0,
+            start_timestamp,
+            end_timestamp,
+            last_exchange_timestamp: trade.exchange_timestamp,
+        }
+    }
+
+    fn add_trade(&mut self, trade: &Trade) {
+        self.high = self.high.max(trade.last_price);
+        self.low = self.low.min(trade.last_price);
+        self.close = trade.last_price;
+        self.volume += trade.last_size;
+        self.notional += trade.last_price * trade.last_size;
+        self.trade_count += 1;
+        self.last_exchange_timestamp = trade.exchange_timestamp;
+    }
+
+    fn is_complete(&self, bar_type: &BarType) -> bool {
+        match bar_type {
+            BarType::Time(_) => false,
+            BarType::Tick(trades) => self.trade_count >= *trades,
+            BarType::Volume(volume) => self.volume >= *volume,
+            BarType::Dollar(notional) => self.notional >= *notional,
+        }
+    }
+
+    fn into_bar(
+        self,
+        exchange: Exchange,
+        symbol: Symbol,
+        bar_type: &BarType,
+        received_timestamp: Timestamp,
+    ) -> Bar {
+        let vwap = match self.volume > 0.0 {
+            true => Some(self.notional / self.volume),
+            false => None,
+        };
+        Bar {
+            event_id: None,
+            symbol,
+            exchange,
+            bar_type: Some(bar_type.clone()),
+            open: self.open,
+            high: self.high,
+            low: self.low,
+            close: self.close,
+            volume: self.volume,
+            vwap,
+            trade_count: self.trade_count,
+            start_timestamp: self.start_timestamp,
+            exchange_timestamp: self.end_timestamp.unwrap_or(self.last_exchange_timestamp),
+            received_timestamp,
+        }
+    }
+}
+
+/// Builds bars per (exchange, symbol) from trades. Time bars are closed at the end of interval:
+/// by `on_timestamp`, by the first trade after it or by `flush`, intervals without trades produce no bar.
+/// Other bars are closed by the trade which reaches threshold, so the last bar can exceed it
+#[derive(Debug, Clone)]
+pub struct BarAggregator {
+    bar_type: BarType,
+    bars: HashMap<(Exchange, Symbol), PartialBar>,
+}
+
+impl BarAggregator {
+    pub fn new(bar_type: BarType) -> Self {
+        Self {
+            bar_type,
+            bars: HashMap::new(),
+        }
+    }
+
+    pub fn bar_type(&self) -> &BarType {
+        &self.bar_type
+    }
+
+    /// Closes time bars which intervals ended by `ts`, sorted by close time
+    pub fn on_timestamp(&mut self, ts: Timestamp) -> Vec<Bar> {
+        self.close_bars(
+            |bar| matches!(bar.end_timestamp, Some(end) if end <= ts),
+            Some(ts),
+        )
+    }
+
+    /// Closes all unfinished bars, e.g. when market data is over, sorted by close time.
+    /// Time bar is closed at the end of its interval and other bars at their last trade
+    pub fn flush(&mut self) -> Vec<Bar> {
+        self.close_bars(|_| true, None)
+    }
+
+    // bars are received at received_ts or at their close time if it's not set
+    fn close_bars<F: Fn(&PartialBar) -> bool>(
+        &mut self,
+        filter: F,
+        received_ts: Option<Timestamp>,
+    ) -> Vec<Bar> {
+        let closed_keys: Vec<(Exchange, Symbol)> = self
+            .bars
+            .iter()
+            .filter(|(_, bar)| filter(bar))
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        let mut closed_bars: Vec<Bar> = closed_keys
+            .into_iter()
+            .map(|key| {
+                let bar = self.bars.remove(&key).unwrap();
+                let received_ts = received_ts
+                    .unwrap_or_else(|| bar.end_timestamp.unwrap_or(bar.last_exchange_timestamp));
+                bar.into_bar(key.0, key.1, &self.bar_type, received_ts)
+            })
+            .collect();
+        closed_bars.sort_by(|a, b| {
+            (a.exchange_timestamp, &a.exchange, &a.symbol).cmp(&(
+                b.exchange_timestamp,
+                &b.exchange,
+                &b.symbol,
+            ))
+        });
+        closed_bars
+    }
+
+    /// Adds trade to the bar of its instrument. Returns time bar closed before this trade
+    /// or bar of other type completed by it
+    pub fn on_trade(&mut self, trade: &Trade) -> Option<Bar> {
+        let key = (trade.exchange.clone(), trade.symbol.clone());
+        let mut closed_bar = None;
+
+        if let BarType::Time(interval) = self.bar_type {
+            let interval = interval.max(1);
+            let start_ts = trade.exchange_timestamp - trade.exchange_timestamp % interval;
+            let bar_ended = matches!(
+                self.bars.get(&key).and_then(|bar| bar.end_timestamp),
+                Some(end) if end <= trade.exchange_timestamp
+            );
+            if bar_ended {
+                let bar = self.bars.remove(&key).unwrap();
+                closed_bar = Some(bar.into_bar(
+                    key.0.clone(),
+                    key.1.clone(),
+                    &self.bar_type,
+                    trade.received_timestamp,
+                ));
+            }
+            self.bars
+                .entry(key)
+                .or_insert_with(|| PartialBar::new(trade, start_ts, Some(start_ts + interval)))
+                .add_trade(trade);
+            return closed_bar;
+        }
+
+        let bar = self
+            .bars
+            .entry(key.clone())
+            .or_insert_with(|| PartialBar::new(trade, trade.exchange_timestamp, None));
+        bar.add_trade(trade);
+        if bar.is_complete(&self.bar_type) {
+            let bar = self.bars.remove(&key).unwrap();
+            closed_bar = Some(bar.into_bar(key.0, key.1, &self.bar_type, trade.received_timestamp));
+        }
+        closed_bar
+    }
+}
+
+/// Wraps event provider and emits `Event::NewBar` built from its trades.
+/// Time bars are closed by exchange time of provided events and go before the event which closed them,
+/// bars completed by trade go right after it. Unfinished bars are flushed when provider is over
+#[derive(Debug)]
+pub struct BarEventProvider<T: EventProvider> {
+    event_provider: T,
+    aggregators: Vec<BarAggregator>,
+    pending_events: VecDeque<Event>,
+}
+
+impl<T: EventProvider> BarEventProvider<T> {
+    pub fn new(event_provider: T) -> Self {
+        Self {
+            event_provider,
+            aggregators: vec![],
+            pending_events: VecDeque::new(),
+        }
+    }
+
+    pub fn with_bar_aggregator(mut self, aggregator: BarAggregator) -> Self {
+        self.aggregators.push(aggregator);
+        self
+    }
+}
+
+impl<T: EventProvider> EventProvider for BarEventProvider<T> {
+    fn next_event(&mut self) -> Option<Event> {
+        if let Some(event) = self.pending_events.pop_front() {
+            return Some(event);
+        }
+
+        let event = match self.event_provider.next_event() {
+            Some(val) => val,
+            None => {
+                let mut bars: Vec<Bar> = self
+                    .aggregators
+                    .iter_mut()
+                    .flat_map(|aggregator| aggregator.flush())
+                    .collect();
+                bars.sort_by_key(|bar| bar.exchange_timestamp);
+                self.pending_events
+                    .extend(bars.into_iter().map(Event::NewBar));
+                return self.pending_events.pop_front();
+            }
+        };
+        for aggregator in self.aggregators.iter_mut() {
+            let bars = aggregator.on_timestamp(event.exchange_timestamp());
+            self.pending_events
+                .extend(bars.into_iter().map(Event::NewBar));
+        }
+        let trade = match &event {
+            Event::NewMarketTrade(trade) => Some(trade.clone()),
+            _ => None,
+        };
+        self.pending_events.push_back(event);
+        if let Some(trade) = trade {
+            for aggregator in self.aggregators.iter_mut() {
+                if let Some(bar) = aggregator.on_trade(&trade) {
+                    self.pending_events.push_back(Event::NewBar(bar));
+                }
+            }
+        }
+        self.pending_events.pop_front()
+    }
+}
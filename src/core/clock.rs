@@ -0,0 +1,46 @@
+use super::types::Timestamp;
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub trait Clock: Debug + Send + Sync {
+    fn now(&self) -> Timestamp;
+}
+
+/// Milliseconds since unix epoch
+#[derive(Debug, Clone, Default)]
+pub struct WallClock {}
+
+impl Clock for WallClock {
+    fn now(&self) -> Timestamp {
+        match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(val) => val.as_millis() as Timestamp,
+            Err(_) => 0,
+        }
+    }
+}
+
+/// Simulated time, advanced by sim environment to the timestamp of each delivered event.
+/// Clones share the same time
+#[derive(Debug, Clone, Default)]
+pub struct SimClock {
+    now: Arc<AtomicU64>,
+}
+
+impl SimClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Time never goes back: timestamp earlier than current one is ignored
+    pub fn advance_to(&self, ts: Timestamp) {
+        self.now.fetch_max(ts, Ordering::SeqCst);
+    }
+}
+
+impl Clock for SimClock {
+    fn now(&self) -> Timestamp {
+        self.now.load(Ordering::SeqCst)
+    }
+}
@@ -1,10 +1,16 @@
 use crate::core::actions_context::ActionsContext;
-use crate::core::event_loop::{start_event_loop, Actor, EventProvider};
+use crate::core::bar_aggregator::BarAggregator;
+use crate::core::event_loop::{
+    panic_message, start_event_loop, Actor, ActorPanic, EventCounts, EventLoop, EventProvider,
+    StopHandle,
+};
 use crate::core::gateway_router::{ExchangeRequest, GatewayRouter};
 use crate::core::message_bus::{
     start_message_bus, CrossbeamMessageProvider, CrossbeamMessageSender, LoggerMessageHandler,
     Message, MessageHandler, MessageProvider, MessageSender, SimpleMessage,
 };
+use crate::core::order::Order;
+use crate::core::timer::TimerEventProvider;
 use crate::core::types::{Exchange, Latency};
 use crate::sim::broker::{SimBroker, SimBrokerConfig};
 use crate::sim::environment::{SimulatedEnvironment, SimulatedTradingMarketDataProvider};
@@ -13,11 +19,13 @@ use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::sync::{Arc, Mutex};
 use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 use std::{io, thread};
 
 pub struct EngineExecutionInfo {
     pub threads: Vec<io::Result<JoinHandle<()>>>,
     pub exchange_requests_receivers: HashMap<Exchange, Receiver<ExchangeRequest>>,
+    pub stop_handle: StopHandle,
 }
 
 #[derive(Debug)]
@@ -26,6 +34,27 @@ pub enum EngineError {
     Initialization(String),
 }
 
+#[derive(Debug)]
+pub enum RunError {
+    ActorPanic(Box<ActorPanic>),
+    ThreadPanic { thread: String, message: String },
+}
+
+#[derive(Debug, Default)]
+pub struct BrokerState {
+    pub open_orders: Vec<Order>,
+    pub done_orders: Vec<Order>,
+}
+
+/// Outcome of completed sim run. Broker state is empty if event loop thread panicked
+#[derive(Debug)]
+pub struct SimRunResult {
+    pub event_counts: EventCounts,
+    pub duration: Duration,
+    pub brokers: HashMap<Exchange, BrokerState>,
+    pub errors: Vec<RunError>,
+}
+
 pub struct Engine<
     S: Actor<M, MS>,
     M = SimpleMessage,
@@ -41,6 +70,7 @@ pub struct Engine<
     actors: Vec<Arc<Mutex<S>>>,
     message_handlers: Vec<Arc<Mutex<H>>>,
     exchanges: Vec<Exchange>,
+    bar_aggregators: Vec<BarAggregator>,
 }
 
 impl<
@@ -50,6 +80,7 @@ impl<
         H: MessageHandler<M, MS> + 'static,
     > Engine<S, M, MS, H>
 {
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
         Self {
             phantom: Default::default(),
@@ -57,6 +88,7 @@ impl<
             actors: vec![],
             exchanges: vec![],
             message_handlers: vec![],
+            bar_aggregators: vec![],
         }
     }
     pub fn add_actor(&mut self, actor: Arc<Mutex<S>>) {
@@ -67,6 +99,12 @@ impl<
         self.message_handlers.push(message_handler);
     }
 
+    /// Bars are built inside sim environment only. For external event provider
+    /// wrap it into `BarEventProvider`
+    pub fn add_bar_aggregator(&mut self, aggregator: BarAggregator) {
+        self.bar_aggregators.push(aggregator);
+    }
+
     pub fn add_exchange(&mut self, exchange: Exchange) {
         self.exchanges.push(exchange)
     }
@@ -83,20 +121,36 @@ impl<
         let gateway_router = GatewayRouter::new(self.exchanges.clone());
         let exchange_requests_receivers = gateway_router.receivers();
         let actions_context = ActionsContext::new_with_sender(gateway_router, message_sender);
+        let event_provider = Self::with_timers(event_provider, &actions_context);
+        let stop_handle = StopHandle::new();
         let threads = self.start_threads(
             event_provider,
             actions_context,
             Some(message_provider),
             true,
             None,
+            stop_handle.clone(),
         )?;
 
         Ok(EngineExecutionInfo {
             threads,
             exchange_requests_receivers,
+            stop_handle,
         })
     }
 
+    /// External event provider is wrapped to deliver timers by the actions context clock
+    fn with_timers<T: EventProvider>(
+        event_provider: T,
+        actions_context: &ActionsContext<M, MS>,
+    ) -> TimerEventProvider<T> {
+        TimerEventProvider::new(
+            event_provider,
+            actions_context.timer_requests_receiver(),
+            actions_context.clock(),
+        )
+    }
+
     fn start_threads<T: EventProvider + Send + 'static, MP: MessageProvider<M> + Send + 'static>(
         &self,
         event_provider: T,
@@ -104,6 +158,7 @@ impl<
         message_provider: Option<MP>,
         run_messaging: bool,
         terminate_messaging_on_event_loop_stop: Option<bool>,
+        stop_handle: StopHandle,
     ) -> Result<Vec<io::Result<JoinHandle<()>>>, EngineError> {
         if run_messaging && message_provider.is_none() {
             return Err(EngineError::MissedParameter(
@@ -115,32 +170,48 @@ impl<
         {
             let actions_context = actions_context.clone();
             let actors = self.actors.clone();
+            let stop_handle = stop_handle.clone();
             threads.push(
                 thread::Builder::new()
                     .name("event_loop_thread".to_string())
-                    .spawn(move || start_event_loop(event_provider, actors, actions_context)),
+                    .spawn(move || {
+                        start_event_loop(event_provider, actors, actions_context, stop_handle)
+                    }),
             );
         }
 
         if run_messaging {
-            let message_provider = message_provider.unwrap();
-            let message_handlers = self.message_handlers.clone();
-            threads.push(
-                thread::Builder::new()
-                    .name("message_bus_thread".to_string())
-                    .spawn(move || {
-                        start_message_bus(
-                            message_provider,
-                            message_handlers,
-                            actions_context,
-                            terminate_messaging_on_event_loop_stop.unwrap_or(false),
-                        )
-                    }),
-            );
+            threads.push(self.start_message_bus_thread(
+                message_provider.unwrap(),
+                actions_context,
+                terminate_messaging_on_event_loop_stop.unwrap_or(false),
+                stop_handle,
+            ));
         };
 
         Ok(threads)
     }
+
+    fn start_message_bus_thread<MP: MessageProvider<M> + Send + 'static>(
+        &self,
+        message_provider: MP,
+        actions_context: ActionsContext<M, MS>,
+        terminate_on_event_loop_stop: bool,
+        stop_handle: StopHandle,
+    ) -> io::Result<JoinHandle<()>> {
+        let message_handlers = self.message_handlers.clone();
+        thread::Builder::new()
+            .name("message_bus_thread".to_string())
+            .spawn(move || {
+                start_message_bus(
+                    message_provider,
+                    message_handlers,
+                    actions_context,
+                    terminate_on_event_loop_stop,
+                    stop_handle,
+                )
+            })
+    }
 }
 
 impl<
@@ -168,6 +239,9 @@ impl<
             false => (ActionsContext::new(gateway_router), None),
         }
     }
+
+    /// Requests are stamped and timers fire by `WallClock`: only sim environment runs have
+    /// simulated time, even if the event provider replays historical data
     pub fn start_with_event_provider<T: EventProvider + Send + 'static>(
         self,
         event_provider: T,
@@ -177,18 +251,22 @@ impl<
             self.create_actions_context_with_default_message_provider(run_messaging);
 
         let exchange_requests_receivers = actions_context.exchange_requests_receivers();
+        let event_provider = Self::with_timers(event_provider, &actions_context);
 
+        let stop_handle = StopHandle::new();
         let threads = self.start_threads(
             event_provider,
             actions_context,
             message_provider,
             run_messaging,
             None,
+            stop_handle.clone(),
         )?;
 
         Ok(EngineExecutionInfo {
             threads,
             exchange_requests_receivers,
+            stop_handle,
         })
     }
 
@@ -201,7 +279,129 @@ impl<
     ) -> Result<EngineExecutionInfo, EngineError> {
         let (actions_context, message_provider) =
             self.create_actions_context_with_default_message_provider(run_messaging);
+        let sim_env = Self::create_sim_environment(
+            md_provider,
+            default_latency,
+            sim_broker_configs,
+            self.bar_aggregators.clone(),
+            &actions_context,
+        );
+        let actions_context = actions_context.with_clock(Arc::new(sim_env.clock()));
 
+        let stop_handle = StopHandle::new();
+        let threads = self.start_threads(
+            sim_env,
+            actions_context,
+            message_provider,
+            run_messaging,
+            Some(true),
+            stop_handle.clone(),
+        )?;
+
+        Ok(EngineExecutionInfo {
+            threads,
+            exchange_requests_receivers: Default::default(),
+            stop_handle,
+        })
+    }
+
+    /// Runs sim to completion: blocks until event loop and message bus threads are joined.
+    /// Run can be interrupted from another thread with a clone of `stop_handle`
+    pub fn run_with_sim_environment<T: SimulatedTradingMarketDataProvider + Send + 'static>(
+        self,
+        md_provider: T,
+        default_latency: Option<Latency>,
+        sim_broker_configs: HashMap<Exchange, SimBrokerConfig>,
+        run_messaging: bool,
+        stop_handle: StopHandle,
+    ) -> Result<SimRunResult, EngineError> {
+        let started_at = Instant::now();
+        let (actions_context, message_provider) =
+            self.create_actions_context_with_default_message_provider(run_messaging);
+        let sim_env = Self::create_sim_environment(
+            md_provider,
+            default_latency,
+            sim_broker_configs,
+            self.bar_aggregators.clone(),
+            &actions_context,
+        );
+        let actions_context = actions_context.with_clock(Arc::new(sim_env.clock()));
+
+        let event_loop_thread = {
+            let actions_context = actions_context.clone();
+            let actors = self.actors.clone();
+            let stop_handle = stop_handle.clone();
+            thread::Builder::new()
+                .name("event_loop_thread".to_string())
+                .spawn(move || {
+                    let mut event_loop = EventLoop::new(sim_env, actors, actions_context)
+                        .with_stop_handle(stop_handle)
+                        .with_catch_panics(true);
+                    event_loop.run();
+                    let event_counts = event_loop.event_counts().clone();
+                    let actor_panic = event_loop.actor_panic().cloned();
+                    (event_counts, actor_panic, event_loop.into_event_provider())
+                })
+                .map_err(|err| EngineError::Initialization(format!("{:?}", err)))?
+        };
+
+        let message_bus_thread = match message_provider {
+            Some(message_provider) => Some(
+                self.start_message_bus_thread(message_provider, actions_context, true, stop_handle)
+                    .map_err(|err| EngineError::Initialization(format!("{:?}", err)))?,
+            ),
+            None => None,
+        };
+
+        let mut result = SimRunResult {
+            event_counts: Default::default(),
+            duration: Default::default(),
+            brokers: Default::default(),
+            errors: vec![],
+        };
+
+        match event_loop_thread.join() {
+            Ok((event_counts, actor_panic, sim_env)) => {
+                result.event_counts = event_counts;
+                if let Some(actor_panic) = actor_panic {
+                    result
+                        .errors
+                        .push(RunError::ActorPanic(Box::new(actor_panic)));
+                }
+                for (exchange, broker) in sim_env.brokers() {
+                    let state = BrokerState {
+                        open_orders: broker.open_orders(),
+                        done_orders: broker.done_orders(),
+                    };
+                    result.brokers.insert(exchange.clone(), state);
+                }
+            }
+            Err(payload) => result.errors.push(RunError::ThreadPanic {
+                thread: "event_loop_thread".to_string(),
+                message: panic_message(payload.as_ref()),
+            }),
+        }
+
+        if let Some(message_bus_thread) = message_bus_thread {
+            if let Err(payload) = message_bus_thread.join() {
+                result.errors.push(RunError::ThreadPanic {
+                    thread: "message_bus_thread".to_string(),
+                    message: panic_message(payload.as_ref()),
+                });
+            }
+        }
+
+        result.duration = started_at.elapsed();
+        Ok(result)
+    }
+
+    fn create_sim_environment<T: SimulatedTradingMarketDataProvider>(
+        md_provider: T,
+        default_latency: Option<Latency>,
+        sim_broker_configs: HashMap<Exchange, SimBrokerConfig>,
+        bar_aggregators: Vec<BarAggregator>,
+        actions_context: &ActionsContext<SimpleMessage, CrossbeamMessageSender<SimpleMessage>>,
+    ) -> SimulatedEnvironment<T, SimBroker> {
         let mut sim_env = SimulatedEnvironment::new(md_provider, default_latency);
 
         for (exchange, gw_receiver) in actions_context.exchange_requests_receivers() {
@@ -215,18 +415,10 @@ impl<
                 panic!("{:?}", err)
             };
         }
-
-        let threads = self.start_threads(
-            sim_env,
-            actions_context,
-            message_provider,
-            run_messaging,
-            Some(true),
-        )?;
-
-        Ok(EngineExecutionInfo {
-            threads,
-            exchange_requests_receivers: Default::default(),
-        })
+        for aggregator in bar_aggregators {
+            sim_env.add_bar_aggregator(aggregator);
+        }
+        sim_env.set_timer_requests_receiver(actions_context.timer_requests_receiver());
+        sim_env
     }
 }
@@ -1,7 +1,10 @@
 use super::events::Event;
 use crate::core::actions_context::ActionsContext;
 use crate::core::message_bus::{Message, MessageSender};
-use log::error;
+use log::{error, info};
+use std::any::Any;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 
 pub trait EventProvider {
@@ -12,10 +15,85 @@ pub trait Actor<M: Message, MS: MessageSender<M>>: Send {
     fn on_event(&mut self, event: &Event, actions_context: &mut ActionsContext<M, MS>);
 }
 
+/// Signals running event loop and message bus to exit.
+/// Event loop checks it between events, so a blocked event provider delays the stop
+#[derive(Debug, Clone, Default)]
+pub struct StopHandle {
+    stop_requested: Arc<AtomicBool>,
+}
+
+impl StopHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn stop(&self) {
+        self.stop_requested.store(true, Ordering::SeqCst)
+    }
+
+    pub fn is_stop_requested(&self) -> bool {
+        self.stop_requested.load(Ordering::SeqCst)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventCounts {
+    pub total: u64,
+    pub market_trades: u64,
+    pub quotes: u64,
+    pub order_book_updates: u64,
+    pub bars: u64,
+    pub responses: u64,
+    pub order_updates: u64,
+    pub timers: u64,
+}
+
+impl EventCounts {
+    fn add(&mut self, event: &Event) {
+        self.total += 1;
+        match event {
+            Event::NewMarketTrade(_) => self.market_trades += 1,
+            Event::NewQuote(_) => self.quotes += 1,
+            Event::NewOrderBookUpdate(_) => self.order_book_updates += 1,
+            Event::NewBar(_) => self.bars += 1,
+            Event::UDSOrderUpdate(_) => self.order_updates += 1,
+            Event::Timer(_) => self.timers += 1,
+            Event::ResponseNewOrderAccepted(_)
+            | Event::ResponseNewOrderRejected(_)
+            | Event::ResponseCancelOrderAccepted(_)
+            | Event::ResponseCancelOrderRejected(_)
+            | Event::ResponseAmendOrderAccepted(_)
+            | Event::ResponseAmendOrderRejected(_) => self.responses += 1,
+        }
+    }
+}
+
+/// Panic raised by actor while processing event. If panics are caught, event loop stops
+/// after the first one
+#[derive(Debug, Clone)]
+pub struct ActorPanic {
+    pub event: Event,
+    pub message: String,
+}
+
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic payload".to_string()
+    }
+}
+
 pub struct EventLoop<T: EventProvider, S: Actor<M, MS>, M: Message, MS: MessageSender<M>> {
     event_provider: T,
     actors: Vec<Arc<Mutex<S>>>,
     actions_context: ActionsContext<M, MS>,
+    event_counts: EventCounts,
+    actor_panic: Option<ActorPanic>,
+    stop_handle: StopHandle,
+    catch_panics: bool,
 }
 
 impl<T: EventProvider, S: Actor<M, MS>, M: Message, MS: MessageSender<M>> EventLoop<T, S, M, MS> {
@@ -28,20 +106,53 @@ impl<T: EventProvider, S: Actor<M, MS>, M: Message, MS: MessageSender<M>> EventL
             event_provider,
             actors,
             actions_context,
+            event_counts: Default::default(),
+            actor_panic: None,
+            stop_handle: Default::default(),
+            catch_panics: false,
         }
     }
 
+    pub fn with_stop_handle(mut self, stop_handle: StopHandle) -> Self {
+        self.stop_handle = stop_handle;
+        self
+    }
+
+    /// If set, actor panic is caught and stored as `ActorPanic` instead of unwinding
+    /// the event loop thread
+    pub fn with_catch_panics(mut self, catch_panics: bool) -> Self {
+        self.catch_panics = catch_panics;
+        self
+    }
+
     pub fn run(&mut self) {
         'event_loop: loop {
+            if self.stop_handle.is_stop_requested() {
+                info!("stop requested, event loop is stopping");
+                break 'event_loop;
+            }
             let event = self.event_provider.next_event();
             if event.is_none() {
                 break 'event_loop;
             }
             let event = event.unwrap();
+            self.event_counts.add(&event);
             for actor in &mut self.actors {
                 match &mut actor.lock() {
+                    Ok(actor) if !self.catch_panics => {
+                        actor.on_event(&event, &mut self.actions_context)
+                    }
                     Ok(actor) => {
-                        actor.on_event(&event, &mut self.actions_context);
+                        let actions_context = &mut self.actions_context;
+                        let result = catch_unwind(AssertUnwindSafe(|| {
+                            actor.on_event(&event, actions_context)
+                        }));
+                        if let Err(payload) = result {
+                            let message = panic_message(payload.as_ref());
+                            error!("actor panicked: {}. event: {:?}", &message, &event);
+                            self.actor_panic = Some(ActorPanic { event, message });
+                            break 'event_loop;
+                        }
                     }
                     Err(err) => {
                         error!("failed to process event because of mutex lock error: {:?}. event: {:?}", err, &event)
@@ -58,6 +169,18 @@ impl<T: EventProvider, S: Actor<M, MS>, M: Message, MS: MessageSender<M>> EventL
     pub fn get_actors(&self) -> &Vec<Arc<Mutex<S>>> {
         &self.actors
     }
+
+    pub fn event_counts(&self) -> &EventCounts {
+        &self.event_counts
+    }
+
+    pub fn actor_panic(&self) -> Option<&ActorPanic> {
+        self.actor_panic.as_ref()
+    }
+
+    pub fn into_event_provider(self) -> T {
+        self.event_provider
+    }
 }
 
 pub fn start_event_loop<
@@ -69,7 +192,9 @@ pub fn start_event_loop<
     event_provider: T,
     actors: Vec<Arc<Mutex<S>>>,
     actions_context: ActionsContext<M, MS>,
+    stop_handle: StopHandle,
 ) {
-    let mut event_loop = EventLoop::new(event_provider, actors, actions_context);
+    let mut event_loop =
+        EventLoop::new(event_provider, actors, actions_context).with_stop_handle(stop_handle);
     event_loop.run()
 }
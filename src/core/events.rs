@@ -1,6 +1,7 @@
-use super::market_data::{MarketDataEvent, Quote, Trade};
+use super::market_data::{Bar, MarketDataEvent, OrderBookUpdate, Quote, Trade};
+use super::timer::Timer;
 use super::types::{
-    ClientOrderId, EventId, Exchange, ExchangeOrderId, ExchangeRequestID, ExecutionType,
+    Asset, ClientOrderId, EventId, Exchange, ExchangeOrderId, ExchangeRequestID, ExecutionType,
     OrderStatus, OrderType, Side, Symbol, TimeInForce, Timestamp,
 };
 use serde::{Deserialize, Serialize};
@@ -9,11 +10,16 @@ use serde::{Deserialize, Serialize};
 pub enum Event {
     NewMarketTrade(Trade),
     NewQuote(Quote),
+    NewOrderBookUpdate(OrderBookUpdate),
+    NewBar(Bar),
     ResponseNewOrderAccepted(NewOrderAccepted),
     ResponseNewOrderRejected(NewOrderRejected),
     ResponseCancelOrderAccepted(CancelOrderAccepted),
     ResponseCancelOrderRejected(CancelOrderRejected),
+    ResponseAmendOrderAccepted(AmendOrderAccepted),
+    ResponseAmendOrderRejected(AmendOrderRejected),
     UDSOrderUpdate(OrderUpdate),
+    Timer(Timer),
 }
 
 impl From<MarketDataEvent> for Event {
@@ -21,6 +27,8 @@ impl From<MarketDataEvent> for Event {
         match md_event {
             MarketDataEvent::NewMarketTrade(trade) => Self::NewMarketTrade(trade),
             MarketDataEvent::NewQuote(quote) => Self::NewQuote(quote),
+            MarketDataEvent::NewOrderBookUpdate(update) => Self::NewOrderBookUpdate(update),
+            MarketDataEvent::NewBar(bar) => Self::NewBar(bar),
         }
     }
 }
@@ -30,11 +38,16 @@ impl Event {
         match self {
             Self::NewMarketTrade(t) => t.received_timestamp,
             Self::NewQuote(q) => q.received_timestamp,
+            Self::NewOrderBookUpdate(b) => b.received_timestamp,
+            Self::NewBar(b) => b.received_timestamp,
             Self::ResponseNewOrderAccepted(r) => r.timestamp,
             Self::ResponseNewOrderRejected(r) => r.timestamp,
             Self::ResponseCancelOrderAccepted(r) => r.timestamp,
             Self::ResponseCancelOrderRejected(r) => r.timestamp,
+            Self::ResponseAmendOrderAccepted(r) => r.timestamp,
+            Self::ResponseAmendOrderRejected(r) => r.timestamp,
             Self::UDSOrderUpdate(o) => o.timestamp,
+            Self::Timer(t) => t.timestamp,
         }
     }
 
@@ -42,11 +55,16 @@ impl Event {
         match self {
             Self::NewMarketTrade(t) => t.exchange_timestamp,
             Self::NewQuote(q) => q.exchange_timestamp,
+            Self::NewOrderBookUpdate(b) => b.exchange_timestamp,
+            Self::NewBar(b) => b.exchange_timestamp,
             Self::ResponseNewOrderAccepted(r) => r.exchange_timestamp,
             Self::ResponseNewOrderRejected(r) => r.exchange_timestamp,
             Self::ResponseCancelOrderAccepted(r) => r.exchange_timestamp,
             Self::ResponseCancelOrderRejected(r) => r.exchange_timestamp,
+            Self::ResponseAmendOrderAccepted(r) => r.exchange_timestamp,
+            Self::ResponseAmendOrderRejected(r) => r.exchange_timestamp,
             Self::UDSOrderUpdate(o) => o.exchange_timestamp,
+            Self::Timer(t) => t.timestamp,
         }
     }
 
@@ -54,11 +72,17 @@ impl Event {
         match self {
             Self::NewMarketTrade(t) => t.exchange.clone(),
             Self::NewQuote(q) => q.exchange.clone(),
+            Self::NewOrderBookUpdate(b) => b.exchange.clone(),
+            Self::NewBar(b) => b.exchange.clone(),
             Self::ResponseNewOrderAccepted(r) => r.exchange.clone(),
             Self::ResponseNewOrderRejected(r) => r.exchange.clone(),
             Self::ResponseCancelOrderAccepted(r) => r.exchange.clone(),
             Self::ResponseCancelOrderRejected(r) => r.exchange.clone(),
+            Self::ResponseAmendOrderAccepted(r) => r.exchange.clone(),
+            Self::ResponseAmendOrderRejected(r) => r.exchange.clone(),
             Self::UDSOrderUpdate(o) => o.exchange.clone(),
+            // timers are local and don't belong to any exchange
+            Self::Timer(_) => Exchange::new(),
         }
     }
 
@@ -66,11 +90,16 @@ impl Event {
         match self {
             Self::NewMarketTrade(t) => t.symbol.clone(),
             Self::NewQuote(q) => q.symbol.clone(),
+            Self::NewOrderBookUpdate(b) => b.symbol.clone(),
+            Self::NewBar(b) => b.symbol.clone(),
             Self::ResponseNewOrderAccepted(r) => r.symbol.clone(),
             Self::ResponseNewOrderRejected(r) => r.symbol.clone(),
             Self::ResponseCancelOrderAccepted(r) => r.symbol.clone(),
             Self::ResponseCancelOrderRejected(r) => r.symbol.clone(),
+            Self::ResponseAmendOrderAccepted(r) => r.symbol.clone(),
+            Self::ResponseAmendOrderRejected(r) => r.symbol.clone(),
             Self::UDSOrderUpdate(o) => o.symbol.clone(),
+            Self::Timer(_) => Symbol::new(),
         }
     }
 }
@@ -113,6 +142,33 @@ pub struct CancelOrderAccepted {
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct CancelOrderRejected {
+    pub event_id: EventId,
+    pub request_id: Option<ExchangeRequestID>,
+    pub timestamp: Timestamp,
+    pub exchange_timestamp: Timestamp,
+    pub client_order_id: Option<ClientOrderId>,
+    pub exchange_order_id: Option<ExchangeOrderId>,
+    pub reason: String,
+    pub exchange: Exchange,
+    pub symbol: Symbol,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AmendOrderAccepted {
+    pub event_id: EventId,
+    pub request_id: Option<ExchangeRequestID>,
+    pub timestamp: Timestamp,
+    pub exchange_timestamp: Timestamp,
+    pub client_order_id: ClientOrderId,
+    pub exchange_order_id: ExchangeOrderId,
+    pub price: Option<f64>,
+    pub quantity: f64,
+    pub exchange: Exchange,
+    pub symbol: Symbol,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AmendOrderRejected {
     pub event_id: EventId,
     pub request_id: Option<ExchangeRequestID>,
     pub timestamp: Timestamp,
@@ -146,4 +202,8 @@ pub struct OrderUpdate {
     pub accumulated_filled_qty: Option<f64>,
     pub last_filled_price: Option<f64>,
     pub last_trade_time: Option<Timestamp>,
+    #[serde(default)]
+    pub commission: Option<f64>,
+    #[serde(default)]
+    pub commission_asset: Option<Asset>,
 }
@@ -10,6 +10,8 @@ use std::collections::HashMap;
 pub enum ExchangeRequest {
     NewOrder(NewOrderRequest),
     CancelOrder(CancelOrderRequest),
+    AmendOrder(AmendOrderRequest),
+    CancelAll(CancelAllRequest),
 }
 
 impl ExchangeRequest {
@@ -17,6 +19,17 @@ impl ExchangeRequest {
         match self {
             ExchangeRequest::NewOrder(r) => r.creation_ts,
             ExchangeRequest::CancelOrder(r) => r.creation_ts,
+            ExchangeRequest::AmendOrder(r) => r.creation_ts,
+            ExchangeRequest::CancelAll(r) => r.creation_ts,
+        }
+    }
+
+    pub fn set_creation_ts(&mut self, ts: Timestamp) {
+        match self {
+            ExchangeRequest::NewOrder(r) => r.creation_ts = ts,
+            ExchangeRequest::CancelOrder(r) => r.creation_ts = ts,
+            ExchangeRequest::AmendOrder(r) => r.creation_ts = ts,
+            ExchangeRequest::CancelAll(r) => r.creation_ts = ts,
         }
     }
 }
@@ -36,13 +49,36 @@ pub struct NewOrderRequest {
     pub creation_ts: Timestamp,
 }
 
+/// Cancels order by exchange order id or by client order id. If both are set,
+/// exchange order id takes precedence
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
 pub struct CancelOrderRequest {
+    pub request_id: ExchangeRequestID,
+    pub client_order_id: Option<ClientOrderId>,
+    pub exchange_order_id: Option<ExchangeOrderId>,
+    pub exchange: Exchange,
+    pub symbol: Symbol,
+    pub creation_ts: Timestamp,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct AmendOrderRequest {
     pub request_id: ExchangeRequestID,
     pub client_order_id: ClientOrderId,
     pub exchange_order_id: ExchangeOrderId,
     pub exchange: Exchange,
     pub symbol: Symbol,
+    pub new_price: Option<f64>,
+    pub new_quantity: Option<f64>,
+    pub creation_ts: Timestamp,
+}
+
+/// Cancels all open orders on exchange. If symbol is set, only orders for this symbol are cancelled
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct CancelAllRequest {
+    pub request_id: ExchangeRequestID,
+    pub exchange: Exchange,
+    pub symbol: Option<Symbol>,
     pub creation_ts: Timestamp,
 }
 
@@ -74,6 +110,7 @@ impl GatewayRouter {
         self.receivers.clone()
     }
 
+    #[allow(unknown_lints, clippy::result_large_err)]
     pub(crate) fn send_request(
         &mut self,
         request: ExchangeRequest,
@@ -81,9 +118,12 @@ impl GatewayRouter {
         match request {
             ExchangeRequest::NewOrder(r) => self.send_order(r),
             ExchangeRequest::CancelOrder(c) => self.cancel_order(c),
+            ExchangeRequest::AmendOrder(a) => self.amend_order(a),
+            ExchangeRequest::CancelAll(c) => self.cancel_all(c),
         }
     }
 
+    #[allow(unknown_lints, clippy::result_large_err)]
     pub(crate) fn send_order(
         &mut self,
         request: NewOrderRequest,
@@ -98,6 +138,7 @@ impl GatewayRouter {
         }
     }
 
+    #[allow(unknown_lints, clippy::result_large_err)]
     pub(crate) fn cancel_order(
         &mut self,
         request: CancelOrderRequest,
@@ -111,4 +152,34 @@ impl GatewayRouter {
             Err(err) => Err(GatewayRouterError::SendError(err)),
         }
     }
+
+    #[allow(unknown_lints, clippy::result_large_err)]
+    pub(crate) fn amend_order(
+        &mut self,
+        request: AmendOrderRequest,
+    ) -> Result<(), GatewayRouterError> {
+        let sender = match self.senders.get(&request.exchange) {
+            Some(val) => val,
+            None => return Err(GatewayRouterError::UnknownExchange),
+        };
+        match sender.send(ExchangeRequest::AmendOrder(request)) {
+            Ok(_) => Ok(()),
+            Err(err) => Err(GatewayRouterError::SendError(err)),
+        }
+    }
+
+    #[allow(unknown_lints, clippy::result_large_err)]
+    pub(crate) fn cancel_all(
+        &mut self,
+        request: CancelAllRequest,
+    ) -> Result<(), GatewayRouterError> {
+        let sender = match self.senders.get(&request.exchange) {
+            Some(val) => val,
+            None => return Err(GatewayRouterError::UnknownExchange),
+        };
+        match sender.send(ExchangeRequest::CancelAll(request)) {
+            Ok(_) => Ok(()),
+            Err(err) => Err(GatewayRouterError::SendError(err)),
+        }
+    }
 }
@@ -1,10 +1,12 @@
-use super::types::{EventId, Exchange, Symbol, Timestamp};
+use super::types::{EventId, Exchange, Latency, Symbol, Timestamp};
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum MarketDataEvent {
     NewMarketTrade(Trade),
     NewQuote(Quote),
+    NewOrderBookUpdate(OrderBookUpdate),
+    NewBar(Bar),
 }
 
 impl MarketDataEvent {
@@ -12,6 +14,8 @@ impl MarketDataEvent {
         match self {
             Self::NewQuote(q) => q.received_timestamp = ts,
             Self::NewMarketTrade(t) => t.received_timestamp = ts,
+            Self::NewOrderBookUpdate(b) => b.received_timestamp = ts,
+            Self::NewBar(b) => b.received_timestamp = ts,
         }
     }
 
@@ -22,6 +26,12 @@ impl MarketDataEvent {
             })
             | Self::NewMarketTrade(Trade {
                 exchange_timestamp, ..
+            })
+            | Self::NewOrderBookUpdate(OrderBookUpdate {
+                exchange_timestamp, ..
+            })
+            | Self::NewBar(Bar {
+                exchange_timestamp, ..
             }) => *exchange_timestamp,
         }
     }
@@ -32,6 +42,12 @@ impl MarketDataEvent {
             })
             | Self::NewMarketTrade(Trade {
                 received_timestamp, ..
+            })
+            | Self::NewOrderBookUpdate(OrderBookUpdate {
+                received_timestamp, ..
+            })
+            | Self::NewBar(Bar {
+                received_timestamp, ..
             }) => *received_timestamp,
         }
     }
@@ -39,7 +55,9 @@ impl MarketDataEvent {
     pub fn exchange(&self) -> Exchange {
         match self {
             Self::NewQuote(Quote { exchange, .. })
-            | Self::NewMarketTrade(Trade { exchange, .. }) => exchange.clone(),
+            | Self::NewMarketTrade(Trade { exchange, .. })
+            | Self::NewOrderBookUpdate(OrderBookUpdate { exchange, .. })
+            | Self::NewBar(Bar { exchange, .. }) => exchange.clone(),
         }
     }
 
@@ -47,6 +65,8 @@ impl MarketDataEvent {
         match self {
             Self::NewQuote(q) => q.symbol.clone(),
             Self::NewMarketTrade(t) => t.symbol.clone(),
+            Self::NewOrderBookUpdate(b) => b.symbol.clone(),
+            Self::NewBar(b) => b.symbol.clone(),
         }
     }
 }
@@ -79,3 +99,72 @@ pub struct Quote {
     pub exchange_timestamp: Timestamp,
     pub received_timestamp: Timestamp,
 }
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct PriceLevel {
+    pub price: f64,
+    pub size: f64,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub enum OrderBookUpdateType {
+    /// Full book: all levels not listed in update are removed
+    SNAPSHOT,
+    /// Changed levels only: listed size replaces level size, zero size removes level
+    DELTA,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct OrderBookUpdate {
+    pub event_id: Option<EventId>,
+
+    pub symbol: Symbol,
+    pub exchange: Exchange,
+
+    pub update_type: OrderBookUpdateType,
+    /// Exchange sequence number. Delta is expected to follow previous update with sequence + 1
+    pub sequence: u64,
+    pub bids: Vec<PriceLevel>,
+    pub asks: Vec<PriceLevel>,
+
+    pub exchange_timestamp: Timestamp,
+    pub received_timestamp: Timestamp,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub enum BarType {
+    /// Fixed exchange time interval, aligned to multiples of interval
+    Time(Latency),
+    /// Fixed number of trades
+    Tick(u64),
+    /// Traded quantity reaches threshold
+    Volume(f64),
+    /// Traded notional reaches threshold
+    Dollar(f64),
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Bar {
+    pub event_id: Option<EventId>,
+
+    pub symbol: Symbol,
+    pub exchange: Exchange,
+
+    /// Not set for bars which are not built by `BarAggregator`
+    #[serde(default)]
+    pub bar_type: Option<BarType>,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+    #[serde(default)]
+    pub vwap: Option<f64>,
+    #[serde(default)]
+    pub trade_count: u64,
+
+    /// Exchange time bar is opened at. Exchange timestamp is the time bar is closed at
+    pub start_timestamp: Timestamp,
+    pub exchange_timestamp: Timestamp,
+    pub received_timestamp: Timestamp,
+}
@@ -1,4 +1,5 @@
 use crate::core::actions_context::ActionsContext;
+use crate::core::event_loop::StopHandle;
 use crossbeam_channel::{unbounded, Receiver, Sender};
 use log::{error, info, warn};
 use std::collections::HashMap;
@@ -39,6 +40,7 @@ pub struct MessageBus<
     message_handlers_topic_agnostic: Vec<Arc<Mutex<H>>>,
     actions_context: ActionsContext<M, MS>,
     terminate_on_event_loop_stop_message: bool,
+    stop_handle: StopHandle,
 }
 
 impl<M: Message, T: MessageProvider<M>, H: MessageHandler<M, MS>, MS: MessageSender<M>>
@@ -76,9 +78,15 @@ impl<M: Message, T: MessageProvider<M>, H: MessageHandler<M, MS>, MS: MessageSen
             message_handlers_topic_agnostic,
             actions_context,
             terminate_on_event_loop_stop_message,
+            stop_handle: Default::default(),
         }
     }
 
+    pub fn with_stop_handle(mut self, stop_handle: StopHandle) -> Self {
+        self.stop_handle = stop_handle;
+        self
+    }
+
     fn send_message_to_handler(&self, handler: &Arc<Mutex<H>>, message: &M) {
         match &mut handler.lock() {
             Ok(handler) => handler.on_new_message(message, &self.actions_context),
@@ -121,7 +129,10 @@ impl<M: Message, T: MessageProvider<M>, H: MessageHandler<M, MS>, MS: MessageSen
                 }
             };
 
-            if message.is_event_loop_stopped_message() && self.terminate_on_event_loop_stop_message
+            // messages sent before event loop stopped are already drained, channel is FIFO
+            if message.is_event_loop_stopped_message()
+                && (self.terminate_on_event_loop_stop_message
+                    || self.stop_handle.is_stop_requested())
             {
                 return;
             }
@@ -140,6 +151,7 @@ impl<M: Message> CrossbeamMessageSender<M> {
         self.receiver.clone()
     }
 
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
         let (sender, receiver) = unbounded();
         Self { sender, receiver }
@@ -236,12 +248,14 @@ pub fn start_message_bus<
     message_handlers: Vec<Arc<Mutex<H>>>,
     actions_context: ActionsContext<M, MS>,
     terminate_on_event_loop_stop_message: bool,
+    stop_handle: StopHandle,
 ) {
     let mut message_bus = MessageBus::new(
         message_provider,
         message_handlers,
         actions_context,
         terminate_on_event_loop_stop_message,
-    );
+    )
+    .with_stop_handle(stop_handle);
     message_bus.run()
 }
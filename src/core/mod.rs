@@ -1,4 +1,6 @@
 pub mod actions_context;
+pub mod bar_aggregator;
+pub mod clock;
 pub mod engine;
 pub mod event_loop;
 pub mod events;
@@ -6,4 +8,8 @@ pub mod gateway_router;
 pub mod market_data;
 pub mod message_bus;
 pub mod order;
+pub mod order_book;
+pub mod order_manager;
+pub mod position_tracker;
+pub mod timer;
 pub mod types;
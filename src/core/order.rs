@@ -1,9 +1,10 @@
+use super::gateway_router::NewOrderRequest;
 use super::types::{
     ClientOrderId, Exchange, ExchangeOrderId, OrderStatus, OrderType, Side, Symbol, TimeInForce,
     Timestamp,
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Order {
     pub(crate) create_ts: Timestamp,
     pub(crate) update_ts: Timestamp,
@@ -21,3 +22,105 @@ pub struct Order {
     pub(crate) avg_fill_price: Option<f64>,
     pub(crate) status: OrderStatus,
 }
+
+impl Order {
+    pub(crate) fn new_order_from_exchange_request(request: &NewOrderRequest, ts: u64) -> Self {
+        let (price, trigger_price) = match request.r#type {
+            OrderType::LIMIT => (request.price, None),
+            OrderType::MARKET => (None, None),
+            OrderType::STOP => (None, request.trigger_price),
+            OrderType::STOP_LIMIT => (request.price, request.trigger_price),
+            // other types are kept as requested: exchange decides if they are supported
+            OrderType::LIQUIDATION => (request.price, request.trigger_price),
+        };
+
+        Self {
+            create_ts: ts,
+            update_ts: ts,
+            exchange_order_id: None,
+            client_order_id: request.client_order_id.clone(),
+            exchange: request.exchange.clone(),
+            r#type: request.r#type.clone(),
+            time_in_force: request.time_in_force.clone(),
+            price,
+            trigger_price,
+            symbol: request.symbol.clone(),
+            side: request.side.clone(),
+            quantity: request.quantity,
+            filled_quantity: None,
+            avg_fill_price: None,
+            status: OrderStatus::NEW,
+        }
+    }
+
+    pub fn create_ts(&self) -> Timestamp {
+        self.create_ts
+    }
+
+    pub fn update_ts(&self) -> Timestamp {
+        self.update_ts
+    }
+
+    pub fn exchange_order_id(&self) -> Option<&ExchangeOrderId> {
+        self.exchange_order_id.as_ref()
+    }
+
+    pub fn client_order_id(&self) -> &ClientOrderId {
+        &self.client_order_id
+    }
+
+    pub fn exchange(&self) -> &Exchange {
+        &self.exchange
+    }
+
+    pub fn order_type(&self) -> &OrderType {
+        &self.r#type
+    }
+
+    pub fn time_in_force(&self) -> &TimeInForce {
+        &self.time_in_force
+    }
+
+    pub fn price(&self) -> Option<f64> {
+        self.price
+    }
+
+    pub fn trigger_price(&self) -> Option<f64> {
+        self.trigger_price
+    }
+
+    pub fn symbol(&self) -> &Symbol {
+        &self.symbol
+    }
+
+    pub fn side(&self) -> &Side {
+        &self.side
+    }
+
+    pub fn quantity(&self) -> f64 {
+        self.quantity
+    }
+
+    pub fn filled_quantity(&self) -> Option<f64> {
+        self.filled_quantity
+    }
+
+    pub fn avg_fill_price(&self) -> Option<f64> {
+        self.avg_fill_price
+    }
+
+    pub fn status(&self) -> &OrderStatus {
+        &self.status
+    }
+
+    /// Order is open until it reaches one of terminal statuses
+    pub fn is_open(&self) -> bool {
+        matches!(
+            self.status,
+            OrderStatus::PENDING_NEW
+                | OrderStatus::NEW
+                | OrderStatus::PARTIALLY_FILLED
+                | OrderStatus::PENDING_CANCEL
+        )
+    }
+}
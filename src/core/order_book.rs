@@ -0,0 +1,230 @@
+use super::events::Event;
+use super::market_data::{OrderBookUpdate, OrderBookUpdateType, PriceLevel};
+use super::types::{Exchange, Side, Symbol, Timestamp};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderBookError {
+    /// Update belongs to another exchange or symbol
+    WrongInstrument,
+    /// Delta is received before the first snapshot or after sequence gap
+    NoSnapshot,
+    SequenceGap {
+        expected: u64,
+        received: u64,
+    },
+}
+
+type Result<T> = std::result::Result<T, OrderBookError>;
+
+/// f64 price used as BTreeMap key
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Price(f64);
+
+impl Eq for Price {}
+
+impl PartialOrd for Price {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Price {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// Local L2 book of one instrument maintained from `OrderBookUpdate` events.
+/// Queries take book side: BUY for bids and SELL for asks
+#[derive(Debug, Clone)]
+pub struct OrderBook {
+    exchange: Exchange,
+    symbol: Symbol,
+    bids: BTreeMap<Price, f64>,
+    asks: BTreeMap<Price, f64>,
+    // None until the first snapshot and after sequence gap
+    sequence: Option<u64>,
+    exchange_timestamp: Timestamp,
+}
+
+impl OrderBook {
+    pub fn new(exchange: Exchange, symbol: Symbol) -> Self {
+        Self {
+            exchange,
+            symbol,
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+            sequence: None,
+            exchange_timestamp: 0,
+        }
+    }
+
+    pub fn exchange(&self) -> &Exchange {
+        &self.exchange
+    }
+
+    pub fn symbol(&self) -> &Symbol {
+        &self.symbol
+    }
+
+    pub fn sequence(&self) -> Option<u64> {
+        self.sequence
+    }
+
+    pub fn exchange_timestamp(&self) -> Timestamp {
+        self.exchange_timestamp
+    }
+
+    /// True when book is built from snapshot and all deltas after it
+    pub fn is_synced(&self) -> bool {
+        self.sequence.is_some()
+    }
+
+    /// Applies update of this instrument, other events are ignored
+    pub fn on_event(&mut self, event: &Event) -> Result<()> {
+        match event {
+            Event::NewOrderBookUpdate(update)
+                if update.exchange == self.exchange && update.symbol == self.symbol =>
+            {
+                self.apply(update)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Snapshot replaces the book. Delta must follow the last applied sequence: stale delta is
+    /// ignored and gap clears the book, so it stays empty until the next snapshot
+    pub fn apply(&mut self, update: &OrderBookUpdate) -> Result<()> {
+        if update.exchange != self.exchange || update.symbol != self.symbol {
+            return Err(OrderBookError::WrongInstrument);
+        }
+
+        match update.update_type {
+            OrderBookUpdateType::SNAPSHOT => {
+                self.bids.clear();
+                self.asks.clear();
+            }
+            OrderBookUpdateType::DELTA => {
+                let expected = match self.sequence {
+                    Some(val) => val + 1,
+                    None => return Err(OrderBookError::NoSnapshot),
+                };
+                if update.sequence < expected {
+                    return Ok(());
+                }
+                if update.sequence > expected {
+                    self.clear();
+                    return Err(OrderBookError::SequenceGap {
+                        expected,
+                        received: update.sequence,
+                    });
+                }
+            }
+        }
+
+        Self::update_levels(&mut self.bids, &update.bids);
+        Self::update_levels(&mut self.asks, &update.asks);
+        self.sequence = Some(update.sequence);
+        self.exchange_timestamp = update.exchange_timestamp;
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.bids.clear();
+        self.asks.clear();
+        self.sequence = None;
+    }
+
+    pub fn best_bid(&self) -> Option<PriceLevel> {
+        self.levels(&Side::BUY).next()
+    }
+
+    pub fn best_ask(&self) -> Option<PriceLevel> {
+        self.levels(&Side::SELL).next()
+    }
+
+    pub fn mid_price(&self) -> Option<f64> {
+        match (self.best_bid(), self.best_ask()) {
+            (Some(bid), Some(ask)) => Some((bid.price + ask.price) / 2.0),
+            _ => None,
+        }
+    }
+
+    pub fn spread(&self) -> Option<f64> {
+        match (self.best_bid(), self.best_ask()) {
+            (Some(bid), Some(ask)) => Some(ask.price - bid.price),
+            _ => None,
+        }
+    }
+
+    /// Levels from the best price: descending for bids and ascending for asks
+    pub fn levels(&self, side: &Side) -> Box<dyn Iterator<Item = PriceLevel> + '_> {
+        let to_level = |(price, size): (&Price, &f64)| PriceLevel {
+            price: price.0,
+            size: *size,
+        };
+        match side {
+            Side::BUY => Box::new(self.bids.iter().rev().map(to_level)),
+            Side::SELL => Box::new(self.asks.iter().map(to_level)),
+        }
+    }
+
+    /// Up to `depth` best levels
+    pub fn best_levels(&self, side: &Side, depth: usize) -> Vec<PriceLevel> {
+        self.levels(side).take(depth).collect()
+    }
+
+    /// Size at exactly this price level, zero if there is no level
+    pub fn depth_at_price(&self, side: &Side, price: f64) -> f64 {
+        let levels = match side {
+            Side::BUY => &self.bids,
+            Side::SELL => &self.asks,
+        };
+        levels.get(&Price(price)).copied().unwrap_or(0.0)
+    }
+
+    /// Average price of taking `size` from this side of the book walking from the best level.
+    /// None if book doesn't have enough size
+    pub fn vwap_to_size(&self, side: &Side, size: f64) -> Option<f64> {
+        if size <= 0.0 {
+            return None;
+        }
+        let mut remaining = size;
+        let mut notional = 0.0;
+        for level in self.levels(side) {
+            let taken = level.size.min(remaining);
+            notional += taken * level.price;
+            remaining -= taken;
+            if remaining <= 0.0 {
+                return Some(notional / size);
+            }
+        }
+        None
+    }
+
+    /// Removes size taken by simulated order. Level stays reduced until update changes it
+    pub(crate) fn take_size(&mut self, side: &Side, price: f64, size: f64) {
+        let levels = match side {
+            Side::BUY => &mut self.bids,
+            Side::SELL => &mut self.asks,
+        };
+        if let Some(level_size) = levels.get_mut(&Price(price)) {
+            *level_size -= size;
+            if *level_size <= 0.0 {
+                levels.remove(&Price(price));
+            }
+        }
+    }
+
+    fn update_levels(levels: &mut BTreeMap<Price, f64>, updates: &[PriceLevel]) {
+        for level in updates {
+            if level.size > 0.0 {
+                levels.insert(Price(level.price), level.size);
+            } else {
+                levels.remove(&Price(level.price));
+            }
+        }
+    }
+}
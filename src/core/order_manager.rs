@@ -0,0 +1,269 @@
+use super::events::{
+    AmendOrderAccepted, CancelOrderAccepted, CancelOrderRejected, Event, NewOrderAccepted,
+    NewOrderRejected, OrderUpdate,
+};
+use super::gateway_router::{CancelAllRequest, CancelOrderRequest, NewOrderRequest};
+use super::order::Order;
+use super::types::{ClientOrderId, ExchangeOrderId, OrderStatus, OrderType, TimeInForce};
+use log::{debug, warn};
+use std::collections::HashMap;
+
+/// Tracks state of orders sent by strategy.
+/// Outgoing requests are registered with `on_*_request` methods before they are sent,
+/// responses and order updates are applied with `on_event`
+#[derive(Debug, Default)]
+pub struct OrderManager {
+    orders: HashMap<ClientOrderId, Order>,
+}
+
+impl OrderManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn on_new_order_request(&mut self, request: &NewOrderRequest) {
+        let mut order = Order::new_order_from_exchange_request(request, request.creation_ts);
+        order.status = OrderStatus::PENDING_NEW;
+        debug!("new pending order: {:?}", &order);
+        self.orders.insert(request.client_order_id.clone(), order);
+    }
+
+    pub fn on_cancel_order_request(&mut self, request: &CancelOrderRequest) {
+        let client_order_id = match self.find_client_order_id(
+            request.client_order_id.as_ref(),
+            request.exchange_order_id.as_ref(),
+        ) {
+            Some(val) => val,
+            None => {
+                warn!("cancel request for unknown order: {:?}", request);
+                return;
+            }
+        };
+        let order = self.orders.get_mut(&client_order_id).unwrap();
+        if order.is_open() {
+            order.status = OrderStatus::PENDING_CANCEL;
+            order.update_ts = order.update_ts.max(request.creation_ts);
+        }
+    }
+
+    pub fn on_cancel_all_request(&mut self, request: &CancelAllRequest) {
+        for order in self.orders.values_mut() {
+            let symbol_matched = match &request.symbol {
+                Some(symbol) => &order.symbol == symbol,
+                None => true,
+            };
+            if order.exchange == request.exchange && symbol_matched && order.is_open() {
+                order.status = OrderStatus::PENDING_CANCEL;
+                order.update_ts = order.update_ts.max(request.creation_ts);
+            }
+        }
+    }
+
+    pub fn on_event(&mut self, event: &Event) {
+        match event {
+            Event::ResponseNewOrderAccepted(e) => self.on_new_order_accepted(e),
+            Event::ResponseNewOrderRejected(e) => self.on_new_order_rejected(e),
+            Event::ResponseCancelOrderAccepted(e) => self.on_cancel_order_accepted(e),
+            Event::ResponseCancelOrderRejected(e) => self.on_cancel_order_rejected(e),
+            Event::ResponseAmendOrderAccepted(e) => self.on_amend_order_accepted(e),
+            Event::UDSOrderUpdate(e) => self.on_order_update(e),
+            _ => {}
+        }
+    }
+
+    pub fn order(&self, client_order_id: &str) -> Option<&Order> {
+        self.orders.get(client_order_id)
+    }
+
+    pub fn order_by_exchange_order_id(&self, exchange_order_id: &str) -> Option<&Order> {
+        self.orders
+            .values()
+            .find(|o| o.exchange_order_id.as_deref() == Some(exchange_order_id))
+    }
+
+    pub fn orders(&self) -> impl Iterator<Item = &Order> {
+        self.orders.values()
+    }
+
+    pub fn open_orders(&self) -> impl Iterator<Item = &Order> {
+        self.orders.values().filter(|o| o.is_open())
+    }
+
+    pub fn open_orders_by_exchange<'a>(
+        &'a self,
+        exchange: &'a str,
+    ) -> impl Iterator<Item = &'a Order> + 'a {
+        self.open_orders().filter(move |o| o.exchange == exchange)
+    }
+
+    pub fn open_orders_by_symbol<'a>(
+        &'a self,
+        exchange: &'a str,
+        symbol: &'a str,
+    ) -> impl Iterator<Item = &'a Order> + 'a {
+        self.open_orders_by_exchange(exchange)
+            .filter(move |o| o.symbol == symbol)
+    }
+
+    /// Removes orders in terminal statuses and returns them
+    pub fn remove_done_orders(&mut self) -> Vec<Order> {
+        let done_ids: Vec<ClientOrderId> = self
+            .orders
+            .iter()
+            .filter(|(_, o)| !o.is_open())
+            .map(|(k, _)| k.clone())
+            .collect();
+        done_ids
+            .iter()
+            .filter_map(|id| self.orders.remove(id))
+            .collect()
+    }
+
+    fn find_client_order_id(
+        &self,
+        client_order_id: Option<&ClientOrderId>,
+        exchange_order_id: Option<&ExchangeOrderId>,
+    ) -> Option<ClientOrderId> {
+        if let Some(client_order_id) = client_order_id {
+            if self.orders.contains_key(client_order_id) {
+                return Some(client_order_id.clone());
+            }
+        }
+        exchange_order_id
+            .and_then(|id| self.order_by_exchange_order_id(id))
+            .map(|o| o.client_order_id.clone())
+    }
+
+    fn on_new_order_accepted(&mut self, event: &NewOrderAccepted) {
+        let order = match self.orders.get_mut(&event.client_order_id) {
+            Some(val) => val,
+            None => {
+                warn!("new order accepted for unknown order: {:?}", event);
+                return;
+            }
+        };
+        order.exchange_order_id = Some(event.exchange_order_id.clone());
+        if order.status == OrderStatus::PENDING_NEW {
+            order.status = OrderStatus::NEW;
+        }
+        order.update_ts = order.update_ts.max(event.timestamp);
+    }
+
+    fn on_new_order_rejected(&mut self, event: &NewOrderRejected) {
+        match self.orders.get_mut(&event.client_order_id) {
+            Some(order) => {
+                order.status = OrderStatus::REJECTED;
+                order.update_ts = order.update_ts.max(event.timestamp);
+            }
+            None => warn!("new order rejected for unknown order: {:?}", event),
+        }
+    }
+
+    fn on_cancel_order_accepted(&mut self, event: &CancelOrderAccepted) {
+        match self.orders.get_mut(&event.client_order_id) {
+            Some(order) => {
+                if order.is_open() {
+                    order.status = OrderStatus::CANCELED;
+                }
+                order.update_ts = order.update_ts.max(event.timestamp);
+            }
+            None => warn!("cancel accepted for unknown order: {:?}", event),
+        }
+    }
+
+    fn on_cancel_order_rejected(&mut self, event: &CancelOrderRejected) {
+        let client_order_id = match self.find_client_order_id(
+            event.client_order_id.as_ref(),
+            event.exchange_order_id.as_ref(),
+        ) {
+            Some(val) => val,
+            None => {
+                warn!("cancel rejected for unknown order: {:?}", event);
+                return;
+            }
+        };
+        let order = self.orders.get_mut(&client_order_id).unwrap();
+        if order.status == OrderStatus::PENDING_CANCEL {
+            // order is still alive, so it returns to the status it had before cancel request
+            order.status = match order.filled_quantity {
+                Some(_) => OrderStatus::PARTIALLY_FILLED,
+                None => OrderStatus::NEW,
+            };
+        }
+        order.update_ts = order.update_ts.max(event.timestamp);
+    }
+
+    fn on_amend_order_accepted(&mut self, event: &AmendOrderAccepted) {
+        match self.orders.get_mut(&event.client_order_id) {
+            Some(order) => {
+                order.price = event.price;
+                order.quantity = event.quantity;
+                order.update_ts = order.update_ts.max(event.timestamp);
+            }
+            None => warn!("amend accepted for unknown order: {:?}", event),
+        }
+    }
+
+    fn on_order_update(&mut self, event: &OrderUpdate) {
+        let client_order_id = match self.find_client_order_id(
+            event.client_order_id.as_ref(),
+            event.exchange_order_id.as_ref(),
+        ) {
+            Some(val) => val,
+            None => match &event.client_order_id {
+                // order wasn't sent through this manager, so it is tracked from its first update
+                Some(client_order_id) => {
+                    let order = Self::order_from_update(client_order_id, event);
+                    self.orders.insert(client_order_id.clone(), order);
+                    client_order_id.clone()
+                }
+                None => {
+                    warn!("order update without client order id: {:?}", event);
+                    return;
+                }
+            },
+        };
+
+        let order = self.orders.get_mut(&client_order_id).unwrap();
+        if event.exchange_order_id.is_some() {
+            order.exchange_order_id = event.exchange_order_id.clone();
+        }
+        if let Some(order_type) = &event.order_type {
+            order.r#type = order_type.clone();
+        }
+        order.price = event.original_price;
+        order.trigger_price = event.stop_price;
+        order.quantity = event.original_qty;
+        order.filled_quantity = event.accumulated_filled_qty;
+        order.avg_fill_price = event.average_price;
+        let keep_pending_cancel = order.status == OrderStatus::PENDING_CANCEL
+            && matches!(
+                event.order_status,
+                OrderStatus::NEW | OrderStatus::PARTIALLY_FILLED
+            );
+        if !keep_pending_cancel {
+            order.status = event.order_status.clone();
+        }
+        order.update_ts = order.update_ts.max(event.timestamp);
+    }
+
+    fn order_from_update(client_order_id: &ClientOrderId, event: &OrderUpdate) -> Order {
+        Order {
+            create_ts: event.timestamp,
+            update_ts: event.timestamp,
+            exchange_order_id: event.exchange_order_id.clone(),
+            client_order_id: client_order_id.clone(),
+            exchange: event.exchange.clone(),
+            r#type: event.order_type.clone().unwrap_or(OrderType::LIMIT),
+            time_in_force: event.time_in_force.clone().unwrap_or(TimeInForce::GTC),
+            price: event.original_price,
+            trigger_price: event.stop_price,
+            symbol: event.symbol.clone(),
+            side: event.side.clone(),
+            quantity: event.original_qty,
+            filled_quantity: event.accumulated_filled_qty,
+            avg_fill_price: event.average_price,
+            status: event.order_status.clone(),
+        }
+    }
+}
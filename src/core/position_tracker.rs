@@ -0,0 +1,148 @@
+use super::events::{Event, OrderUpdate};
+use super::types::{Exchange, ExecutionType, Side, Symbol};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Position {
+    pub exchange: Exchange,
+    pub symbol: Symbol,
+    /// Signed net quantity: positive for long position, negative for short
+    pub quantity: f64,
+    pub avg_entry_price: Option<f64>,
+    pub realized_pnl: f64,
+    pub unrealized_pnl: f64,
+    pub mark_price: Option<f64>,
+    pub commission: f64,
+}
+
+impl Position {
+    fn new(exchange: Exchange, symbol: Symbol) -> Self {
+        Self {
+            exchange,
+            symbol,
+            quantity: 0.0,
+            avg_entry_price: None,
+            realized_pnl: 0.0,
+            unrealized_pnl: 0.0,
+            mark_price: None,
+            commission: 0.0,
+        }
+    }
+
+    fn add_fill(&mut self, side: &Side, quantity: f64, price: f64) {
+        let signed_quantity = match side {
+            Side::BUY => quantity,
+            Side::SELL => -quantity,
+        };
+        let avg_entry_price = self.avg_entry_price.unwrap_or(price);
+
+        if self.quantity == 0.0 || self.quantity.signum() == signed_quantity.signum() {
+            let total_quantity = self.quantity.abs() + quantity;
+            self.avg_entry_price =
+                Some((avg_entry_price * self.quantity.abs() + price * quantity) / total_quantity);
+            self.quantity += signed_quantity;
+        } else {
+            let closed_quantity = self.quantity.abs().min(quantity);
+            self.realized_pnl +=
+                closed_quantity * (price - avg_entry_price) * self.quantity.signum();
+            self.quantity += signed_quantity;
+            self.avg_entry_price = if self.quantity == 0.0 {
+                None
+            } else if quantity > closed_quantity {
+                // position is flipped, remaining part is opened at fill price
+                Some(price)
+            } else {
+                Some(avg_entry_price)
+            };
+        }
+
+        if self.mark_price.is_none() {
+            self.mark_price = Some(price);
+        }
+        self.update_unrealized_pnl();
+    }
+
+    fn mark(&mut self, price: f64) {
+        self.mark_price = Some(price);
+        self.update_unrealized_pnl();
+    }
+
+    fn update_unrealized_pnl(&mut self) {
+        self.unrealized_pnl = match (self.avg_entry_price, self.mark_price) {
+            (Some(avg_entry_price), Some(mark_price)) => {
+                self.quantity * (mark_price - avg_entry_price)
+            }
+            _ => 0.0,
+        };
+    }
+}
+
+/// Keeps net position per (exchange, symbol) built from fills in `UDSOrderUpdate`
+/// and marks it to market on quotes (mid price), trades (last price) and bars (close price)
+#[derive(Debug, Default)]
+pub struct PositionTracker {
+    positions: HashMap<(Exchange, Symbol), Position>,
+}
+
+impl PositionTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn on_event(&mut self, event: &Event) {
+        match event {
+            Event::UDSOrderUpdate(update) => self.on_order_update(update),
+            Event::NewQuote(quote) => self.mark(
+                &quote.exchange,
+                &quote.symbol,
+                (quote.bid + quote.ask) / 2.0,
+            ),
+            Event::NewMarketTrade(trade) => {
+                self.mark(&trade.exchange, &trade.symbol, trade.last_price)
+            }
+            Event::NewBar(bar) => self.mark(&bar.exchange, &bar.symbol, bar.close),
+            _ => {}
+        }
+    }
+
+    pub fn position(&self, exchange: &str, symbol: &str) -> Option<&Position> {
+        self.positions
+            .get(&(exchange.to_string(), symbol.to_string()))
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = &Position> {
+        self.positions.values()
+    }
+
+    pub fn realized_pnl(&self) -> f64 {
+        self.positions.values().map(|p| p.realized_pnl).sum()
+    }
+
+    pub fn unrealized_pnl(&self) -> f64 {
+        self.positions.values().map(|p| p.unrealized_pnl).sum()
+    }
+
+    fn on_order_update(&mut self, update: &OrderUpdate) {
+        if update.execution_type != ExecutionType::TRADE {
+            return;
+        }
+        let (quantity, price) = match (update.last_filled_qty, update.last_filled_price) {
+            (Some(quantity), Some(price)) => (quantity, price),
+            _ => return,
+        };
+
+        let position = self
+            .positions
+            .entry((update.exchange.clone(), update.symbol.clone()))
+            .or_insert_with(|| Position::new(update.exchange.clone(), update.symbol.clone()));
+        position.add_fill(&update.side, quantity, price);
+        position.commission += update.commission.unwrap_or(0.0);
+    }
+
+    fn mark(&mut self, exchange: &Exchange, symbol: &Symbol, price: f64) {
+        if let Some(position) = self.positions.get_mut(&(exchange.clone(), symbol.clone())) {
+            position.mark(price);
+        }
+    }
+}
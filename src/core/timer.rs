@@ -0,0 +1,125 @@
+use super::clock::Clock;
+use super::event_loop::EventProvider;
+use super::events::Event;
+use super::types::{Latency, Timestamp};
+use crossbeam_channel::Receiver;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+pub type TimerId = u64;
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Timer {
+    pub timer_id: TimerId,
+    pub timestamp: Timestamp,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimerRequest {
+    /// Fires at fire_ts and then every interval if it is set
+    Schedule {
+        timer_id: TimerId,
+        fire_ts: Timestamp,
+        interval: Option<Latency>,
+    },
+    Cancel(TimerId),
+}
+
+/// Scheduled timers ordered by fire timestamp. Timers with equal timestamp fire in id order
+#[derive(Debug, Default)]
+pub struct TimerQueue {
+    scheduled: BTreeMap<(Timestamp, TimerId), Option<Latency>>,
+    fire_ts_by_id: HashMap<TimerId, Timestamp>,
+}
+
+impl TimerQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Timer scheduled before `now` fires at `now`, so timers never go back in time
+    pub fn on_request(&mut self, request: TimerRequest, now: Timestamp) {
+        match request {
+            TimerRequest::Schedule {
+                timer_id,
+                fire_ts,
+                interval,
+            } => {
+                self.cancel(timer_id);
+                self.schedule(timer_id, fire_ts.max(now), interval);
+            }
+            TimerRequest::Cancel(timer_id) => self.cancel(timer_id),
+        }
+    }
+
+    pub fn next_fire_ts(&self) -> Option<Timestamp> {
+        self.scheduled.keys().next().map(|(ts, _)| *ts)
+    }
+
+    /// Removes the earliest timer. Recurring timer is scheduled again after its interval
+    pub fn pop(&mut self) -> Option<Timer> {
+        let (&(fire_ts, timer_id), &interval) = self.scheduled.iter().next()?;
+        self.cancel(timer_id);
+        if let Some(interval) = interval {
+            self.schedule(timer_id, fire_ts + interval, Some(interval));
+        }
+        Some(Timer {
+            timer_id,
+            timestamp: fire_ts,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scheduled.is_empty()
+    }
+
+    fn schedule(&mut self, timer_id: TimerId, fire_ts: Timestamp, interval: Option<Latency>) {
+        self.scheduled.insert((fire_ts, timer_id), interval);
+        self.fire_ts_by_id.insert(timer_id, fire_ts);
+    }
+
+    fn cancel(&mut self, timer_id: TimerId) {
+        if let Some(fire_ts) = self.fire_ts_by_id.remove(&timer_id) {
+            self.scheduled.remove(&(fire_ts, timer_id));
+        }
+    }
+}
+
+/// Wraps external event provider and delivers timers scheduled through actions context
+/// as `Event::Timer` once clock reaches their fire timestamp. Due timers are checked before
+/// each event is requested, so blocked event provider delays them until its next event
+pub struct TimerEventProvider<T: EventProvider> {
+    event_provider: T,
+    timer_requests_receiver: Receiver<TimerRequest>,
+    timers: TimerQueue,
+    clock: Arc<dyn Clock>,
+}
+
+impl<T: EventProvider> TimerEventProvider<T> {
+    pub fn new(
+        event_provider: T,
+        timer_requests_receiver: Receiver<TimerRequest>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        Self {
+            event_provider,
+            timer_requests_receiver,
+            timers: TimerQueue::new(),
+            clock,
+        }
+    }
+}
+
+impl<T: EventProvider> EventProvider for TimerEventProvider<T> {
+    fn next_event(&mut self) -> Option<Event> {
+        let now = self.clock.now();
+        while let Ok(request) = self.timer_requests_receiver.try_recv() {
+            self.timers.on_request(request, now);
+        }
+        match self.timers.next_fire_ts() {
+            Some(fire_ts) if fire_ts <= now => self.timers.pop().map(Event::Timer),
+            _ => self.event_provider.next_event(),
+        }
+    }
+}
@@ -8,12 +8,15 @@ pub type Timestamp = u64;
 pub type Latency = u64;
 pub type EventId = String;
 pub type ExchangeRequestID = String;
+pub type Asset = String;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
 pub enum OrderType {
     MARKET,
     LIMIT,
-    STOP,
+    STOP,       // market order after trigger price is touched
+    STOP_LIMIT, // limit order after trigger price is touched
     LIQUIDATION,
 }
 
@@ -32,6 +35,7 @@ pub enum ExecutionType {
     CALCULATED, //Liquidation Execution
     EXPIRED,
     TRADE,
+    AMENDMENT,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -43,11 +47,14 @@ pub enum Side {
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[allow(non_camel_case_types)]
 pub enum OrderStatus {
+    PENDING_NEW, // request is sent, but not acknowledged by exchange yet
     NEW,
     PARTIALLY_FILLED,
     FILLED,
+    PENDING_CANCEL, // cancel request is sent, but not acknowledged by exchange yet
     CANCELED,
     EXPIRED,
+    REJECTED,
     NEW_INSURANCE, //Liquidation with Insurance Fund
     NEW_ADL,       // Counterparty Liquidation`
 }
@@ -1,53 +1,102 @@
 use super::environment::SimulatedBroker;
+use super::fees::FeeSchedule;
+use super::latency::{ConstantLatency, LatencyModel};
+use super::slippage::SlippageModel;
 use crate::core::events::{
-    CancelOrderAccepted, CancelOrderRejected, Event, NewOrderAccepted, NewOrderRejected,
-    OrderUpdate,
+    AmendOrderAccepted, AmendOrderRejected, CancelOrderAccepted, CancelOrderRejected, Event,
+    NewOrderAccepted, NewOrderRejected, OrderUpdate,
 };
-use crate::core::gateway_router::{CancelOrderRequest, ExchangeRequest, NewOrderRequest};
-use crate::core::market_data::MarketDataEvent;
+use crate::core::gateway_router::{
+    AmendOrderRequest, CancelAllRequest, CancelOrderRequest, ExchangeRequest, NewOrderRequest,
+};
+use crate::core::market_data::{Bar, MarketDataEvent, OrderBookUpdate, PriceLevel, Quote, Trade};
 use crate::core::order::Order;
+use crate::core::order_book::OrderBook;
 use crate::core::types::{
-    EventId, Exchange, ExecutionType, Latency, OrderStatus, OrderType, Side, Timestamp,
+    ClientOrderId, EventId, Exchange, ExchangeOrderId, ExecutionType, Latency, OrderStatus,
+    OrderType, Side, Symbol, TimeInForce, Timestamp,
 };
 use crossbeam_channel::Receiver;
-use log::debug;
-use std::collections::HashMap;
+use log::{debug, warn};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
 
 type InternalID = u64;
 
+fn opposite_side(side: &Side) -> Side {
+    match side {
+        Side::BUY => Side::SELL,
+        Side::SELL => Side::BUY,
+    }
+}
+
 #[derive(Debug)]
 enum Error {
     UnreachableStatus,
 }
 
-impl Order {
-    fn new_order_from_exchange_request(request: &NewOrderRequest, ts: u64) -> Self {
-        let (price, trigger_price) = match request.r#type {
-            OrderType::LIMIT => (request.price, None),
-            OrderType::MARKET => (None, None),
-            OrderType::STOP => (None, request.trigger_price),
-            _ => unimplemented!(),
-        };
+/// Size filled on the current md event per order side, so orders share its liquidity
+#[derive(Debug, Default)]
+struct FilledSize {
+    buy: f64,
+    sell: f64,
+}
 
-        Self {
-            create_ts: ts,
-            update_ts: ts,
-            exchange_order_id: None,
-            client_order_id: request.client_order_id.clone(),
-            exchange: request.exchange.clone(),
-            r#type: request.r#type.clone(),
-            time_in_force: request.time_in_force.clone(),
-            price,
-            trigger_price,
-            symbol: request.symbol.clone(),
-            side: request.side.clone(),
-            quantity: request.quantity,
-            filled_quantity: None,
-            avg_fill_price: None,
-            status: OrderStatus::NEW,
+impl FilledSize {
+    fn get(&self, side: &Side) -> f64 {
+        match side {
+            Side::BUY => self.buy,
+            Side::SELL => self.sell,
+        }
+    }
+
+    fn add(&mut self, side: &Side, size: f64) {
+        match side {
+            Side::BUY => self.buy += size,
+            Side::SELL => self.sell += size,
         }
     }
+}
+
+/// Md event orders are matched against in top of book and bars execution models
+#[derive(Debug, Clone, Copy)]
+enum TopOfBook<'a> {
+    Trade(&'a Trade),
+    Quote(&'a Quote),
+}
+
+/// Price marketable order of the side takes liquidity at
+fn touch_price(top: TopOfBook, side: &Side) -> f64 {
+    match top {
+        TopOfBook::Trade(t) => t.last_price,
+        TopOfBook::Quote(q) => match side {
+            Side::BUY => q.ask,
+            Side::SELL => q.bid,
+        },
+    }
+}
 
+/// Limit order takes liquidity on arrival if it crosses the opposite quote or trade went
+/// through its price. Trade at order price doesn't show it, so such order is passive
+fn crosses_on_arrival(top: TopOfBook, side: &Side, order_price: f64) -> bool {
+    match (top, side) {
+        (TopOfBook::Quote(q), Side::BUY) => q.ask <= order_price,
+        (TopOfBook::Quote(q), Side::SELL) => q.bid >= order_price,
+        (TopOfBook::Trade(t), Side::BUY) => t.last_price < order_price,
+        (TopOfBook::Trade(t), Side::SELL) => t.last_price > order_price,
+    }
+}
+
+/// Md event open orders are matched against. Bars are executed as synthetic trades
+#[derive(Debug, Clone, Copy)]
+enum MatchingEvent<'a> {
+    TopOfBook(TopOfBook<'a>),
+    BookUpdate(&'a OrderBookUpdate),
+}
+
+impl Order {
     fn set_confirmed_by_exchange(
         &mut self,
         exchange_order_id: String,
@@ -62,10 +111,66 @@ impl Order {
         }
     }
 
+    fn remaining_quantity(&self) -> f64 {
+        self.quantity - self.filled_quantity.unwrap_or(0.0)
+    }
+
+    fn add_fill(&mut self, quantity: f64, price: f64, fill_ts: u64) {
+        let filled_before = self.filled_quantity.unwrap_or(0.0);
+        let (filled_after, status) = if quantity >= self.remaining_quantity() {
+            (self.quantity, OrderStatus::FILLED)
+        } else {
+            (filled_before + quantity, OrderStatus::PARTIALLY_FILLED)
+        };
+
+        let avg_fill_price = match self.avg_fill_price {
+            Some(avg) => {
+                (avg * filled_before + price * (filled_after - filled_before)) / filled_after
+            }
+            None => price,
+        };
+
+        self.filled_quantity = Some(filled_after);
+        self.avg_fill_price = Some(avg_fill_price);
+        self.status = status;
+        self.update_ts = self.update_ts.max(fill_ts);
+    }
+
+    /// Returns true if order loses its queue priority: price is changed or quantity is increased
+    fn amend(&mut self, new_price: Option<f64>, new_quantity: Option<f64>, amend_ts: u64) -> bool {
+        let mut loses_priority = false;
+        if let Some(price) = new_price {
+            loses_priority |= self.price != Some(price);
+            self.price = Some(price);
+        }
+        if let Some(quantity) = new_quantity {
+            loses_priority |= quantity > self.quantity;
+            self.quantity = quantity;
+        }
+        self.update_ts = self.update_ts.max(amend_ts);
+        loses_priority
+    }
+
+    fn expire(&mut self, expire_ts: u64) {
+        self.status = OrderStatus::EXPIRED;
+        self.update_ts = self.update_ts.max(expire_ts);
+    }
+
+    fn trigger(&mut self, trigger_ts: u64) {
+        // once triggered stop order behaves as plain market or limit order
+        self.r#type = match self.r#type {
+            OrderType::STOP => OrderType::MARKET,
+            OrderType::STOP_LIMIT => OrderType::LIMIT,
+            _ => unreachable!(),
+        };
+        self.update_ts = self.update_ts.max(trigger_ts);
+    }
+
     fn cancel(&mut self, cancel_ts: u64) -> Result<(), Error> {
         if self.status == OrderStatus::FILLED || self.exchange_order_id.is_none() {
             Err(Error::UnreachableStatus)
         } else {
+            self.status = OrderStatus::CANCELED;
             self.update_ts = self.update_ts.max(cancel_ts);
             Ok(())
         }
@@ -80,11 +185,74 @@ struct SimBrokerExchangeRequest {
     exchange_request: ExchangeRequest,
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum FillModel {
+    /// Order is filled in one shot for the whole quantity
+    #[default]
+    FullQuantity,
+    /// Each fill is capped by trade size or quote size on the opposite side, which is shared
+    /// by all orders executed on the same md event. Quote without size doesn't cap fills
+    AvailableSize,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum QueueModel {
+    /// Resting limit order is filled as soon as price touches its level (or crosses it in strict execution)
+    #[default]
+    Disabled,
+    /// Resting limit order is filled only after displayed size ahead of it has traded at its level
+    DisplayedSize,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ExecutionModel {
+    /// Orders are executed against trades and quotes, order book updates are only forwarded
+    #[default]
+    TopOfBook,
+    /// Orders are executed against order book maintained from L2 updates: marketable order walks
+    /// opposite side levels and resting limit order fills as size ahead of it at its level
+    /// is depleted. Fills are always capped by book sizes, so fill model doesn't apply.
+    /// Trades and quotes are only forwarded
+    OrderBookDepth,
+    /// Orders are executed against bars: each bar is replayed as trades at OHLC prices of the path
+    /// and stamped with bar close time. With available size fill model bar volume is shared
+    /// by all fills along the path. Order arriving before the bar is checked against its open,
+    /// so post only order crossing the open expires. Trades, quotes and book updates
+    /// are only forwarded
+    Bars(IntrabarPath),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum IntrabarPath {
+    /// Bar open price only
+    #[default]
+    OpenOnly,
+    /// Open, low, high, close for bars closed up and open, high, low, close for bars closed down,
+    /// so limit and stop orders are executed if high or low touches their price
+    HighLowTouch,
+    /// Path which is the worst one for order side: buy orders see open, high, low, close and
+    /// sell orders see open, low, high, close. Stop orders are triggered before limit orders
+    /// on the same side are filled
+    Pessimistic,
+}
+
+#[derive(Clone, Debug)]
 pub struct SimBrokerConfig {
     strict_execution: bool,
-    wire_latency: Option<Latency>,
-    internal_latency: Option<Latency>,
+    wire_latency: Arc<dyn LatencyModel>,
+    internal_latency: Arc<dyn LatencyModel>,
+    seed: u64,
+    fill_model: FillModel,
+    queue_model: QueueModel,
+    execution_model: ExecutionModel,
+    fee_schedule: Option<FeeSchedule>,
+    slippage_model: Option<Arc<dyn SlippageModel>>,
+}
+
+impl Default for SimBrokerConfig {
+    fn default() -> Self {
+        Self::new(false, None, None)
+    }
 }
 
 impl SimBrokerConfig {
@@ -95,10 +263,57 @@ impl SimBrokerConfig {
     ) -> Self {
         Self {
             strict_execution,
-            wire_latency,
-            internal_latency,
+            wire_latency: Arc::new(ConstantLatency::new(wire_latency.unwrap_or(0))),
+            internal_latency: Arc::new(ConstantLatency::new(internal_latency.unwrap_or(0))),
+            seed: 0,
+            fill_model: FillModel::default(),
+            queue_model: QueueModel::default(),
+            execution_model: ExecutionModel::default(),
+            fee_schedule: None,
+            slippage_model: None,
         }
     }
+
+    pub fn with_wire_latency_model(mut self, wire_latency: Arc<dyn LatencyModel>) -> Self {
+        self.wire_latency = wire_latency;
+        self
+    }
+
+    pub fn with_internal_latency_model(mut self, internal_latency: Arc<dyn LatencyModel>) -> Self {
+        self.internal_latency = internal_latency;
+        self
+    }
+
+    /// Seed of random generator used to sample latencies, so runs with same seed are reproducible
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn with_fill_model(mut self, fill_model: FillModel) -> Self {
+        self.fill_model = fill_model;
+        self
+    }
+
+    pub fn with_queue_model(mut self, queue_model: QueueModel) -> Self {
+        self.queue_model = queue_model;
+        self
+    }
+
+    pub fn with_execution_model(mut self, execution_model: ExecutionModel) -> Self {
+        self.execution_model = execution_model;
+        self
+    }
+
+    pub fn with_fee_schedule(mut self, fee_schedule: FeeSchedule) -> Self {
+        self.fee_schedule = Some(fee_schedule);
+        self
+    }
+
+    pub fn with_slippage_model(mut self, slippage_model: Arc<dyn SlippageModel>) -> Self {
+        self.slippage_model = Some(slippage_model);
+        self
+    }
 }
 
 pub struct SimBroker {
@@ -113,11 +328,28 @@ pub struct SimBroker {
     order_id_mapping: HashMap<String, InternalID>,
     pending_requests: HashMap<InternalID, SimBrokerExchangeRequest>,
     incoming_request_receiver: Receiver<ExchangeRequest>,
-    generated_events: HashMap<InternalID, Event>,
+    generated_events: BTreeMap<InternalID, Event>,
+    queue_positions: HashMap<InternalID, f64>,
+    resting_orders: HashSet<InternalID>,
+    order_books: HashMap<Symbol, OrderBook>,
+    // size of resting order level before the current book update
+    book_level_sizes: HashMap<InternalID, f64>,
+    md_filled_size: FilledSize,
 
-    wire_latency: Latency,
-    internal_latency: Latency,
+    wire_latency: Arc<dyn LatencyModel>,
+    internal_latency: Arc<dyn LatencyModel>,
+    rng: StdRng,
+    last_request_ack_ts: Timestamp,
+    last_user_stream_ts: Timestamp,
+    last_md_received_ts: Timestamp,
+    // md events stamped by sample_market_data_timestamp, but not processed yet
+    stamped_md_events: usize,
     strict_execution: bool,
+    fill_model: FillModel,
+    queue_model: QueueModel,
+    execution_model: ExecutionModel,
+    fee_schedule: Option<FeeSchedule>,
+    slippage_model: Option<Arc<dyn SlippageModel>>,
 }
 
 impl SimBroker {
@@ -137,14 +369,68 @@ impl SimBroker {
             done_orders: HashMap::new(),
             order_id_mapping: HashMap::new(),
             pending_requests: HashMap::new(),
-            generated_events: HashMap::new(),
+            generated_events: BTreeMap::new(),
+            queue_positions: HashMap::new(),
+            resting_orders: HashSet::new(),
+            order_books: HashMap::new(),
+            book_level_sizes: HashMap::new(),
+            md_filled_size: FilledSize::default(),
             incoming_request_receiver,
-            wire_latency: config.wire_latency.unwrap_or(0),
-            internal_latency: config.internal_latency.unwrap_or(0),
+            wire_latency: config.wire_latency,
+            internal_latency: config.internal_latency,
+            rng: StdRng::seed_from_u64(config.seed),
+            last_request_ack_ts: 0,
+            last_user_stream_ts: 0,
+            last_md_received_ts: 0,
+            stamped_md_events: 0,
             strict_execution: config.strict_execution,
+            fill_model: config.fill_model,
+            queue_model: config.queue_model,
+            execution_model: config.execution_model,
+            fee_schedule: config.fee_schedule,
+            slippage_model: config.slippage_model,
         }
     }
 
+    /// Orders which are still open on exchange, sorted by exchange order id
+    pub fn open_orders(&self) -> Vec<Order> {
+        Self::sorted_orders(&self.open_orders)
+    }
+
+    /// Filled, cancelled and expired orders, sorted by exchange order id
+    pub fn done_orders(&self) -> Vec<Order> {
+        Self::sorted_orders(&self.done_orders)
+    }
+
+    fn sorted_orders(orders: &HashMap<InternalID, Order>) -> Vec<Order> {
+        let mut ids: Vec<&InternalID> = orders.keys().collect();
+        ids.sort_unstable();
+        ids.into_iter().map(|id| orders[id].clone()).collect()
+    }
+
+    fn sample_wire_latency(&mut self) -> Latency {
+        self.wire_latency.sample(&mut self.rng)
+    }
+
+    fn sample_internal_latency(&mut self) -> Latency {
+        self.internal_latency.sample(&mut self.rng)
+    }
+
+    fn user_stream_timestamp(&mut self, exchange_ts: Timestamp) -> Timestamp {
+        // user stream is FIFO: event can't be received before previously generated one
+        let ts = (exchange_ts + self.sample_wire_latency()).max(self.last_user_stream_ts);
+        self.last_user_stream_ts = ts;
+        ts
+    }
+
+    fn md_received_timestamp(&mut self, md: &MarketDataEvent) -> Timestamp {
+        // md feed is FIFO: event can't be received before previous one
+        let ts =
+            (md.exchange_timestamp() + self.sample_wire_latency()).max(self.last_md_received_ts);
+        self.last_md_received_ts = ts;
+        ts
+    }
+
     fn next_public_event_id(&mut self) -> EventId {
         self.public_event_id += 1;
         self.public_event_id.to_string()
@@ -157,7 +443,8 @@ impl SimBroker {
 
         debug!("pending requests: {:?}", &self.pending_requests);
 
-        let keys: Vec<InternalID> = self.pending_requests.keys().map(|&k| k).collect();
+        let mut keys: Vec<InternalID> = self.pending_requests.keys().copied().collect();
+        keys.sort_unstable();
         for req_id in keys {
             if self.pending_requests[&req_id].ack_timestamp > ts {
                 continue;
@@ -175,23 +462,56 @@ impl SimBroker {
                 ExchangeRequest::CancelOrder(request) => {
                     self.on_cancel_order_requests(request, wrapped_request.ack_timestamp)
                 }
+                ExchangeRequest::AmendOrder(request) => {
+                    self.on_amend_order_request(request, wrapped_request.ack_timestamp)
+                }
+                ExchangeRequest::CancelAll(request) => {
+                    self.on_cancel_all_request(request, wrapped_request.ack_timestamp)
+                }
             };
         }
     }
 
     fn update_orders_on_md(&mut self, md: &MarketDataEvent) {
+        self.md_filled_size = FilledSize::default();
         // Update order state, generate UDS and put them into buffer
+        let depth_execution = self.execution_model == ExecutionModel::OrderBookDepth;
+        if let (true, MarketDataEvent::NewOrderBookUpdate(update)) = (depth_execution, md) {
+            self.update_order_book(update);
+        }
+
         if self.open_orders.is_empty() {
             return;
         }
+        if !self.executes_on(md) {
+            return;
+        }
+        let event = match md {
+            MarketDataEvent::NewMarketTrade(t) => MatchingEvent::TopOfBook(TopOfBook::Trade(t)),
+            MarketDataEvent::NewQuote(q) => MatchingEvent::TopOfBook(TopOfBook::Quote(q)),
+            MarketDataEvent::NewOrderBookUpdate(b) => MatchingEvent::BookUpdate(b),
+            MarketDataEvent::NewBar(bar) => {
+                if let ExecutionModel::Bars(path) = self.execution_model.clone() {
+                    self.execute_orders_on_bar(bar, &path);
+                }
+                return;
+            }
+        };
+        self.execute_orders(md, event, None);
+    }
 
+    /// Executes open orders of md symbol. If side is set, orders of the other side are skipped
+    fn execute_orders(&mut self, md: &MarketDataEvent, event: MatchingEvent, side: Option<&Side>) {
         let md_symbol = md.symbol();
-        let order_ids_to_check: Vec<InternalID> = self
+        let mut order_ids_to_check: Vec<InternalID> = self
             .open_orders
             .iter()
             .filter(|(_, v)| v.symbol == md_symbol.as_str())
+            .filter(|(_, v)| side.is_none() || side == Some(&v.side))
             .map(|(&k, _)| k)
             .collect();
+        // check orders in the sequence they were accepted by exchange
+        order_ids_to_check.sort_unstable();
 
         if order_ids_to_check.is_empty() {
             return;
@@ -200,64 +520,700 @@ impl SimBroker {
         for internal_id in order_ids_to_check {
             let order = self.open_orders.get(&internal_id).unwrap();
             match &order.r#type {
-                OrderType::LIMIT => self.execute_limit_order(md, internal_id),
+                OrderType::LIMIT | OrderType::MARKET => self.execute_order(md, event, internal_id),
+                OrderType::STOP | OrderType::STOP_LIMIT => {
+                    self.trigger_stop_order(md, event, internal_id)
+                }
                 _ => unimplemented!(),
             }
         }
     }
 
-    fn execute_limit_order(&mut self, md: &MarketDataEvent, internal_order_id: InternalID) {
+    fn execute_orders_on_bar(&mut self, bar: &Bar, intrabar_path: &IntrabarPath) {
+        let (open, high, low, close) = (bar.open, bar.high, bar.low, bar.close);
+        let paths = match intrabar_path {
+            IntrabarPath::OpenOnly => vec![(None, vec![open])],
+            IntrabarPath::HighLowTouch if close >= open => {
+                vec![(None, vec![open, low, high, close])]
+            }
+            IntrabarPath::HighLowTouch => vec![(None, vec![open, high, low, close])],
+            IntrabarPath::Pessimistic => vec![
+                (Some(Side::BUY), vec![open, high, low, close]),
+                (Some(Side::SELL), vec![open, low, high, close]),
+            ],
+        };
+
+        for (side, mut path) in paths {
+            path.dedup();
+            for price in path {
+                let trade = Trade {
+                    event_id: None,
+                    symbol: bar.symbol.clone(),
+                    exchange: bar.exchange.clone(),
+                    last_price: price,
+                    // size filled on the bar is counted once for the whole path
+                    last_size: bar.volume,
+                    exchange_timestamp: bar.exchange_timestamp,
+                    received_timestamp: bar.received_timestamp,
+                };
+                let md = MarketDataEvent::NewMarketTrade(trade.clone());
+                let event = MatchingEvent::TopOfBook(TopOfBook::Trade(&trade));
+                self.execute_orders(&md, event, side.as_ref());
+            }
+        }
+    }
+
+    /// Market data events orders are executed against in configured execution model.
+    /// Other events are only forwarded
+    fn executes_on(&self, md: &MarketDataEvent) -> bool {
+        matches!(
+            (&self.execution_model, md),
+            (
+                ExecutionModel::TopOfBook,
+                MarketDataEvent::NewMarketTrade(_) | MarketDataEvent::NewQuote(_)
+            ) | (
+                ExecutionModel::OrderBookDepth,
+                MarketDataEvent::NewOrderBookUpdate(_)
+            ) | (ExecutionModel::Bars(_), MarketDataEvent::NewBar(_))
+        )
+    }
+
+    fn update_order_book(&mut self, update: &OrderBookUpdate) {
+        let book = self
+            .order_books
+            .entry(update.symbol.clone())
+            .or_insert_with(|| OrderBook::new(update.exchange.clone(), update.symbol.clone()));
+
+        self.book_level_sizes.clear();
+        for internal_id in self.resting_orders.iter() {
+            let order = &self.open_orders[internal_id];
+            if let (true, Some(price)) = (order.symbol == update.symbol, order.price) {
+                let level_size = book.depth_at_price(&order.side, price);
+                self.book_level_sizes.insert(*internal_id, level_size);
+            }
+        }
+
+        if let Err(err) = book.apply(update) {
+            warn!("failed to apply order book update: {:?} {:?}", err, update);
+        }
+    }
+
+    fn execute_order(
+        &mut self,
+        md: &MarketDataEvent,
+        event: MatchingEvent,
+        internal_order_id: InternalID,
+    ) {
+        match event {
+            MatchingEvent::TopOfBook(top) => {
+                self.execute_order_on_top_of_book(md, top, internal_order_id)
+            }
+            MatchingEvent::BookUpdate(_) => self.execute_order_on_book(md, internal_order_id),
+        }
+    }
+
+    fn execute_order_on_top_of_book(
+        &mut self,
+        md: &MarketDataEvent,
+        top: TopOfBook,
+        internal_order_id: InternalID,
+    ) {
         let order = self.open_orders.get(&internal_order_id).unwrap();
         if order.create_ts > md.exchange_timestamp() {
             return;
         }
+
+        if self.resting_orders.contains(&internal_order_id) {
+            match &order.r#type {
+                OrderType::LIMIT => self.execute_limit_order(md, top, internal_order_id),
+                OrderType::MARKET => self.execute_market_order(md, top, internal_order_id),
+                _ => unreachable!(),
+            }
+            return;
+        }
+
+        // first md event after order is created: time in force is applied here
+        let time_in_force = order.time_in_force.clone();
+        let marketable = match order.r#type {
+            OrderType::LIMIT => self.limit_price_matched(top, &order.side, order.price.unwrap()),
+            _ => true,
+        };
+
+        match time_in_force {
+            TimeInForce::GTX => {
+                // post only order expires if it would take liquidity on arrival
+                let crosses_book = match (&order.r#type, order.price) {
+                    (OrderType::LIMIT, Some(price)) => crosses_on_arrival(top, &order.side, price),
+                    _ => true,
+                };
+                if crosses_book {
+                    self.expire_order(md, internal_order_id);
+                    return;
+                }
+            }
+            TimeInForce::FOK => {
+                let fully_available = match self.available_size(top, &order.side) {
+                    Some(size) => size >= order.remaining_quantity(),
+                    None => true,
+                };
+                if !marketable || !fully_available {
+                    self.expire_order(md, internal_order_id);
+                    return;
+                }
+            }
+            _ => {}
+        }
+
+        match &self.open_orders[&internal_order_id].r#type {
+            OrderType::LIMIT => self.execute_limit_order(md, top, internal_order_id),
+            OrderType::MARKET => self.execute_market_order(md, top, internal_order_id),
+            _ => unreachable!(),
+        }
+
+        if !self.open_orders.contains_key(&internal_order_id) {
+            return;
+        }
+
+        match time_in_force {
+            TimeInForce::IOC | TimeInForce::FOK => self.expire_order(md, internal_order_id),
+            TimeInForce::GTC | TimeInForce::GTX => {
+                self.resting_orders.insert(internal_order_id);
+            }
+        }
+    }
+
+    fn expire_order(&mut self, md: &MarketDataEvent, internal_order_id: InternalID) {
+        let exchange_ts = md.exchange_timestamp() + self.sample_internal_latency();
+        let received_ts = self.user_stream_timestamp(exchange_ts);
+        let event_id = self.next_public_event_id();
+        let mut order = self.open_orders.remove(&internal_order_id).unwrap();
+        order.expire(md.exchange_timestamp());
+        debug!("expire order: {:?}", &order);
+
+        let order_update = OrderUpdate {
+            event_id,
+            exchange_timestamp: exchange_ts,
+            timestamp: received_ts,
+            symbol: order.symbol.clone(),
+            exchange: order.exchange.clone(),
+            side: order.side.clone(),
+            client_order_id: Some(order.client_order_id.clone()),
+            exchange_order_id: Some(order.exchange_order_id.as_ref().unwrap().clone()),
+            order_type: Some(order.r#type.clone()),
+            time_in_force: Some(order.time_in_force.clone()),
+            original_qty: order.quantity,
+            original_price: order.price,
+            average_price: order.avg_fill_price,
+            stop_price: order.trigger_price,
+            execution_type: ExecutionType::EXPIRED,
+            order_status: OrderStatus::EXPIRED,
+            last_filled_qty: None,
+            accumulated_filled_qty: order.filled_quantity,
+            last_filled_price: None,
+            last_trade_time: None,
+            commission: None,
+            commission_asset: None,
+        };
+        self.add_generated_event(Event::UDSOrderUpdate(order_update));
+
+        self.queue_positions.remove(&internal_order_id);
+        self.done_orders.insert(internal_order_id, order);
+    }
+
+    fn execute_limit_order(
+        &mut self,
+        md: &MarketDataEvent,
+        top: TopOfBook,
+        internal_order_id: InternalID,
+    ) {
+        let order = self.open_orders.get(&internal_order_id).unwrap();
+        if order.create_ts > md.exchange_timestamp() {
+            return;
+        }
+        if self.queue_model == QueueModel::DisplayedSize {
+            self.execute_queued_limit_order(md, top, internal_order_id);
+            return;
+        }
         let order_price = order.price.unwrap();
-        let filled = match order.side {
-            Side::BUY => match md {
-                MarketDataEvent::NewMarketTrade(t) => {
+        if !self.limit_price_matched(top, &order.side, order_price) {
+            return;
+        }
+
+        let available_size = self.available_size(top, &order.side);
+        let is_maker = self.limit_order_is_maker(top, internal_order_id);
+        let fill_price = self.limit_fill_price(top, internal_order_id, is_maker);
+        self.fill_order(md, internal_order_id, is_maker, fill_price, available_size);
+    }
+
+    /// Resting limit order provides liquidity, on arrival it does unless it crosses md event
+    fn limit_order_is_maker(&self, top: TopOfBook, internal_order_id: InternalID) -> bool {
+        let order = &self.open_orders[&internal_order_id];
+        self.resting_orders.contains(&internal_order_id)
+            || !crosses_on_arrival(top, &order.side, order.price.unwrap())
+    }
+
+    /// Taker limit order is filled at touch price, maker one at its price. Slippage of taker
+    /// fill is applied from touch price and then capped by limit price
+    fn limit_fill_price(
+        &self,
+        top: TopOfBook,
+        internal_order_id: InternalID,
+        is_maker: bool,
+    ) -> f64 {
+        let order = &self.open_orders[&internal_order_id];
+        if is_maker {
+            order.price.unwrap()
+        } else {
+            touch_price(top, &order.side)
+        }
+    }
+
+    fn limit_price_matched(&self, top: TopOfBook, side: &Side, order_price: f64) -> bool {
+        match side {
+            Side::BUY => match top {
+                TopOfBook::Trade(t) => {
                     if self.strict_execution {
                         t.last_price < order_price
                     } else {
                         t.last_price <= order_price
                     }
                 }
-                MarketDataEvent::NewQuote(q) => q.ask <= order_price,
+                TopOfBook::Quote(q) => q.ask <= order_price,
             },
-            Side::SELL => match md {
-                MarketDataEvent::NewMarketTrade(t) => {
+            Side::SELL => match top {
+                TopOfBook::Trade(t) => {
                     if self.strict_execution {
                         t.last_price > order_price
                     } else {
                         t.last_price >= order_price
                     }
                 }
-                MarketDataEvent::NewQuote(q) => q.bid >= order_price,
+                TopOfBook::Quote(q) => q.bid >= order_price,
+            },
+        }
+    }
+
+    fn execute_queued_limit_order(
+        &mut self,
+        md: &MarketDataEvent,
+        top: TopOfBook,
+        internal_order_id: InternalID,
+    ) {
+        let order = self.open_orders.get(&internal_order_id).unwrap();
+        let order_price = order.price.unwrap();
+        let side = order.side.clone();
+
+        match top {
+            TopOfBook::Quote(q) => {
+                let (crossed, level_price, level_size, at_top) = match side {
+                    Side::BUY => (q.ask <= order_price, q.bid, q.bid_size, q.bid < order_price),
+                    Side::SELL => (q.bid >= order_price, q.ask, q.ask_size, q.ask > order_price),
+                };
+                if crossed {
+                    let available_size = self.available_size(top, &side);
+                    let is_maker = self.limit_order_is_maker(top, internal_order_id);
+                    let fill_price = self.limit_fill_price(top, internal_order_id, is_maker);
+                    self.fill_order(md, internal_order_id, is_maker, fill_price, available_size);
+                    return;
+                }
+
+                let displayed_size_ahead = if level_price == order_price {
+                    level_size.unwrap_or(0.0)
+                } else if at_top {
+                    0.0
+                } else {
+                    // order level is behind the best one, so displayed size ahead is unknown
+                    return;
+                };
+
+                // queue ahead of order can only shrink because new orders join behind it
+                let queue_ahead = self
+                    .queue_positions
+                    .entry(internal_order_id)
+                    .or_insert(displayed_size_ahead);
+                *queue_ahead = queue_ahead.min(displayed_size_ahead);
+            }
+            TopOfBook::Trade(t) => {
+                let traded_through = match side {
+                    Side::BUY => t.last_price < order_price,
+                    Side::SELL => t.last_price > order_price,
+                };
+                if traded_through {
+                    let available_size = self.available_size(top, &side);
+                    let is_maker = self.limit_order_is_maker(top, internal_order_id);
+                    let fill_price = self.limit_fill_price(top, internal_order_id, is_maker);
+                    self.fill_order(md, internal_order_id, is_maker, fill_price, available_size);
+                    return;
+                }
+
+                if t.last_price != order_price {
+                    return;
+                }
+
+                // without quote at order level position in queue is unknown
+                let queue_ahead = match self.queue_positions.get_mut(&internal_order_id) {
+                    Some(val) => val,
+                    None => return,
+                };
+
+                if t.last_size <= *queue_ahead {
+                    *queue_ahead -= t.last_size;
+                    return;
+                }
+
+                let size_after_queue = t.last_size - *queue_ahead;
+                *queue_ahead = 0.0;
+                let available_size = self
+                    .available_size(top, &side)
+                    .map(|size| size.min(size_after_queue));
+                self.fill_order(md, internal_order_id, true, order_price, available_size);
+            }
+        }
+    }
+
+    /// Md event size which is not taken yet by orders of the side
+    fn available_size(&self, top: TopOfBook, side: &Side) -> Option<f64> {
+        if self.fill_model == FillModel::FullQuantity {
+            return None;
+        }
+        let size = match top {
+            TopOfBook::Trade(t) => Some(t.last_size),
+            TopOfBook::Quote(q) => match side {
+                Side::BUY => q.ask_size,
+                Side::SELL => q.bid_size,
+            },
+        };
+        size.map(|size| (size - self.md_filled_size.get(side)).max(0.0))
+    }
+
+    fn execute_market_order(
+        &mut self,
+        md: &MarketDataEvent,
+        top: TopOfBook,
+        internal_order_id: InternalID,
+    ) {
+        let order = self.open_orders.get(&internal_order_id).unwrap();
+        if order.create_ts > md.exchange_timestamp() {
+            return;
+        }
+
+        // market order takes liquidity from the opposite side of the first quote after ack
+        // or is executed at the price of the first trade after ack
+        let fill_price = touch_price(top, &order.side);
+
+        let available_size = self.available_size(top, &order.side);
+        self.fill_order(md, internal_order_id, false, fill_price, available_size);
+    }
+
+    fn trigger_stop_order(
+        &mut self,
+        md: &MarketDataEvent,
+        event: MatchingEvent,
+        internal_order_id: InternalID,
+    ) {
+        let order = self.open_orders.get(&internal_order_id).unwrap();
+        if order.create_ts > md.exchange_timestamp() {
+            return;
+        }
+        let trigger_price = order.trigger_price.unwrap();
+        let triggered = match order.side {
+            Side::BUY => match event {
+                MatchingEvent::TopOfBook(TopOfBook::Trade(t)) => t.last_price >= trigger_price,
+                MatchingEvent::TopOfBook(TopOfBook::Quote(q)) => q.ask >= trigger_price,
+                MatchingEvent::BookUpdate(b) => matches!(
+                    self.best_level(&b.symbol, &Side::SELL),
+                    Some(level) if level.price >= trigger_price
+                ),
+            },
+            Side::SELL => match event {
+                MatchingEvent::TopOfBook(TopOfBook::Trade(t)) => t.last_price <= trigger_price,
+                MatchingEvent::TopOfBook(TopOfBook::Quote(q)) => q.bid <= trigger_price,
+                MatchingEvent::BookUpdate(b) => matches!(
+                    self.best_level(&b.symbol, &Side::BUY),
+                    Some(level) if level.price <= trigger_price
+                ),
             },
         };
 
-        if !filled {
+        if !triggered {
+            return;
+        }
+
+        let exchange_ts = md.exchange_timestamp() + self.sample_internal_latency();
+        let received_ts = self.user_stream_timestamp(exchange_ts);
+        let event_id = self.next_public_event_id();
+        let order = self.open_orders.get_mut(&internal_order_id).unwrap();
+        order.trigger(md.exchange_timestamp());
+        debug!("stop order triggered: {:?}", &order);
+
+        let order_update = OrderUpdate {
+            event_id,
+            exchange_timestamp: exchange_ts,
+            timestamp: received_ts,
+            symbol: order.symbol.clone(),
+            exchange: order.exchange.clone(),
+            side: order.side.clone(),
+            client_order_id: Some(order.client_order_id.clone()),
+            exchange_order_id: Some(order.exchange_order_id.as_ref().unwrap().clone()),
+            order_type: Some(order.r#type.clone()),
+            time_in_force: Some(order.time_in_force.clone()),
+            original_qty: order.quantity,
+            original_price: order.price,
+            average_price: None,
+            stop_price: order.trigger_price,
+            execution_type: ExecutionType::NEW,
+            order_status: OrderStatus::NEW,
+            last_filled_qty: None,
+            accumulated_filled_qty: None,
+            last_filled_price: None,
+            last_trade_time: None,
+            commission: None,
+            commission_asset: None,
+        };
+        self.add_generated_event(Event::UDSOrderUpdate(order_update));
+
+        // triggering md event is also the first one which can execute converted order
+        self.execute_order(md, event, internal_order_id);
+    }
+
+    fn best_level(&self, symbol: &str, side: &Side) -> Option<PriceLevel> {
+        self.order_books
+            .get(symbol)
+            .and_then(|book| book.levels(side).next())
+    }
+
+    /// Opposite side levels which order can take without crossing its limit price, best first
+    fn marketable_levels(
+        &self,
+        symbol: &str,
+        side: &Side,
+        limit_price: Option<f64>,
+    ) -> Vec<PriceLevel> {
+        let book = match self.order_books.get(symbol) {
+            Some(val) => val,
+            None => return vec![],
+        };
+        book.levels(&opposite_side(side))
+            .take_while(|level| match (side, limit_price) {
+                (_, None) => true,
+                (Side::BUY, Some(limit)) => level.price <= limit,
+                (Side::SELL, Some(limit)) => level.price >= limit,
+            })
+            .collect()
+    }
+
+    fn execute_order_on_book(&mut self, md: &MarketDataEvent, internal_order_id: InternalID) {
+        let order = self.open_orders.get(&internal_order_id).unwrap();
+        if order.create_ts > md.exchange_timestamp() {
+            return;
+        }
+        let book_synced =
+            matches!(self.order_books.get(&order.symbol), Some(book) if book.is_synced());
+        if !book_synced {
+            // immediate order can't wait for the book, so it expires unfilled
+            let immediate = matches!(order.time_in_force, TimeInForce::IOC | TimeInForce::FOK);
+            if immediate && !self.resting_orders.contains(&internal_order_id) {
+                self.expire_order(md, internal_order_id);
+            }
+            return;
+        }
+
+        if self.resting_orders.contains(&internal_order_id) {
+            match &order.r#type {
+                OrderType::LIMIT => self.execute_resting_order_on_book(md, internal_order_id),
+                OrderType::MARKET => self.take_book_liquidity(md, internal_order_id),
+                _ => unreachable!(),
+            }
+            return;
+        }
+
+        // first book update after order is created: time in force is applied here
+        let time_in_force = order.time_in_force.clone();
+        let marketable_size: f64 = self
+            .marketable_levels(&order.symbol, &order.side, order.price)
+            .iter()
+            .map(|level| level.size)
+            .sum();
+        match time_in_force {
+            TimeInForce::GTX if marketable_size > 0.0 => {
+                self.expire_order(md, internal_order_id);
+                return;
+            }
+            TimeInForce::FOK if marketable_size < order.remaining_quantity() => {
+                self.expire_order(md, internal_order_id);
+                return;
+            }
+            _ => {}
+        }
+
+        self.take_book_liquidity(md, internal_order_id);
+
+        let order = match self.open_orders.get(&internal_order_id) {
+            Some(val) => val,
+            None => return,
+        };
+        match time_in_force {
+            TimeInForce::IOC | TimeInForce::FOK => self.expire_order(md, internal_order_id),
+            TimeInForce::GTC | TimeInForce::GTX => {
+                // order joins the end of its price level
+                if let Some(price) = order.price {
+                    let size_ahead =
+                        self.order_books[&order.symbol].depth_at_price(&order.side, price);
+                    self.queue_positions.insert(internal_order_id, size_ahead);
+                }
+                self.resting_orders.insert(internal_order_id);
+            }
+        }
+    }
+
+    /// Takes opposite side levels up to order limit price, one fill per level at level price
+    fn take_book_liquidity(&mut self, md: &MarketDataEvent, internal_order_id: InternalID) {
+        let order = self.open_orders.get(&internal_order_id).unwrap();
+        let symbol = order.symbol.clone();
+        let side = order.side.clone();
+        let levels = self.marketable_levels(&symbol, &side, order.price);
+
+        for level in levels {
+            let remaining_quantity = match self.open_orders.get(&internal_order_id) {
+                Some(order) => order.remaining_quantity(),
+                None => break,
+            };
+            let quantity = level.size.min(remaining_quantity);
+            self.fill_order(md, internal_order_id, false, level.price, Some(quantity));
+            if let Some(book) = self.order_books.get_mut(&symbol) {
+                book.take_size(&opposite_side(&side), level.price, quantity);
+            }
+        }
+    }
+
+    fn execute_resting_order_on_book(
+        &mut self,
+        md: &MarketDataEvent,
+        internal_order_id: InternalID,
+    ) {
+        let order = self.open_orders.get(&internal_order_id).unwrap();
+        let symbol = order.symbol.clone();
+        let side = order.side.clone();
+        let order_price = order.price.unwrap();
+        let remaining_quantity = order.remaining_quantity();
+
+        // opposite side crossed order price, so order is matched at its price
+        // after size ahead of it at its level
+        let crossing_levels = self.marketable_levels(&symbol, &side, Some(order_price));
+        if !crossing_levels.is_empty() {
+            let crossing_size: f64 = crossing_levels.iter().map(|level| level.size).sum();
+            let queue_ahead = self
+                .queue_positions
+                .get(&internal_order_id)
+                .copied()
+                .unwrap_or(0.0);
+            if let Some(val) = self.queue_positions.get_mut(&internal_order_id) {
+                *val = (queue_ahead - crossing_size).max(0.0);
+            }
+            let fill_size = crossing_size - queue_ahead;
+            if fill_size > 0.0 {
+                self.fill_order(md, internal_order_id, true, order_price, Some(fill_size));
+            }
+
+            // crossing size is taken by queue ahead and the order
+            let mut matched_size = queue_ahead + remaining_quantity;
+            let book = self.order_books.get_mut(&symbol).unwrap();
+            for level in crossing_levels {
+                let quantity = level.size.min(matched_size);
+                book.take_size(&opposite_side(&side), level.price, quantity);
+                matched_size -= quantity;
+            }
+            return;
+        }
+
+        // level size decrease is attributed to the front of the queue: cancels behind the order
+        // and new orders joining behind it are not distinguished
+        let level_size = self.order_books[&symbol].depth_at_price(&side, order_price);
+        let level_size_before = self
+            .book_level_sizes
+            .get(&internal_order_id)
+            .copied()
+            .unwrap_or(level_size);
+        let depleted_size = (level_size_before - level_size).max(0.0);
+        let queue_ahead = self
+            .queue_positions
+            .entry(internal_order_id)
+            .or_insert(level_size);
+        if depleted_size <= *queue_ahead {
+            *queue_ahead = (*queue_ahead - depleted_size).min(level_size);
+            return;
+        }
+
+        let fill_size = depleted_size - *queue_ahead;
+        *queue_ahead = 0.0;
+        self.fill_order(md, internal_order_id, true, order_price, Some(fill_size));
+    }
+
+    fn fill_order(
+        &mut self,
+        md: &MarketDataEvent,
+        internal_order_id: InternalID,
+        is_maker: bool,
+        fill_price: f64,
+        available_size: Option<f64>,
+    ) {
+        let order = self.open_orders.get(&internal_order_id).unwrap();
+        let remaining_quantity = order.remaining_quantity();
+        let fill_quantity = match available_size {
+            Some(size) => size.min(remaining_quantity),
+            None => remaining_quantity,
+        };
+
+        if fill_quantity <= 0.0 {
             return;
         }
 
         debug!(
-            "execute order [{}]. event_ts: {} order_ts:{} order:{:?} event:{:?}",
+            "execute order [{}]. event_ts: {} order_ts:{} fill_qty: {} order:{:?} event:{:?}",
             &order.client_order_id,
             md.exchange_timestamp(),
             &order.update_ts,
+            fill_quantity,
             &order,
             md
         );
 
-        let mut order = self.open_orders.remove(&internal_order_id).unwrap();
-        order.status = OrderStatus::FILLED;
-        order.filled_quantity = Some(order.quantity);
-        order.avg_fill_price = Some(order_price);
-        order.update_ts = md.exchange_timestamp();
+        let fill_price = match (&self.slippage_model, is_maker) {
+            (Some(slippage_model), false) => {
+                let slippage = slippage_model.slippage(&order.side, fill_price, fill_quantity, md);
+                // limit order can't be filled worse than its price
+                match (&order.side, order.price) {
+                    (Side::BUY, Some(limit)) => (fill_price + slippage).min(limit),
+                    (Side::BUY, None) => fill_price + slippage,
+                    (Side::SELL, Some(limit)) => (fill_price - slippage).max(limit),
+                    (Side::SELL, None) => fill_price - slippage,
+                }
+            }
+            _ => fill_price,
+        };
+
+        let (commission, commission_asset) = match &self.fee_schedule {
+            Some(fee_schedule) => {
+                let first_fill = order.filled_quantity.is_none();
+                (
+                    Some(fee_schedule.commission(fill_price, fill_quantity, is_maker, first_fill)),
+                    fee_schedule.commission_asset.clone(),
+                )
+            }
+            None => (None, None),
+        };
+
+        let exchange_ts = md.exchange_timestamp() + self.sample_internal_latency();
+        let received_ts = self.user_stream_timestamp(exchange_ts);
+        let event_id = self.next_public_event_id();
+        let order = self.open_orders.get_mut(&internal_order_id).unwrap();
+        order.add_fill(fill_quantity, fill_price, md.exchange_timestamp());
+        self.md_filled_size.add(&order.side, fill_quantity);
 
         let order_update = OrderUpdate {
-            event_id: self.next_public_event_id(),
-            exchange_timestamp: order.update_ts + self.internal_latency,
-            timestamp: order.update_ts + self.internal_latency + self.wire_latency,
+            event_id,
+            exchange_timestamp: exchange_ts,
+            timestamp: received_ts,
             symbol: order.symbol.clone(),
             exchange: order.exchange.clone(),
             side: order.side.clone(),
@@ -266,30 +1222,69 @@ impl SimBroker {
             order_type: Some(order.r#type.clone()),
             time_in_force: Some(order.time_in_force.clone()),
             original_qty: order.quantity,
-            original_price: Some(order_price),
-            average_price: Some(order_price),
-            stop_price: None,
+            original_price: order.price,
+            average_price: order.avg_fill_price,
+            stop_price: order.trigger_price,
             execution_type: ExecutionType::TRADE,
-            order_status: OrderStatus::FILLED,
-            last_filled_qty: Some(order.quantity),
-            accumulated_filled_qty: Some(order.quantity),
-            last_filled_price: Some(order_price),
+            order_status: order.status.clone(),
+            last_filled_qty: Some(fill_quantity),
+            accumulated_filled_qty: order.filled_quantity,
+            last_filled_price: Some(fill_price),
             last_trade_time: Some(order.update_ts),
+            commission,
+            commission_asset,
         };
+        let order_filled = order.status == OrderStatus::FILLED;
 
         self.add_generated_event(Event::UDSOrderUpdate(order_update));
-        self.done_orders.insert(internal_order_id, order);
+
+        if order_filled {
+            let order = self.open_orders.remove(&internal_order_id).unwrap();
+            self.queue_positions.remove(&internal_order_id);
+            self.resting_orders.remove(&internal_order_id);
+            self.done_orders.insert(internal_order_id, order);
+        }
     }
 
-    fn on_new_order_request(&mut self, request: &NewOrderRequest, ts: Timestamp) {
+    fn validate_new_order_request(&self, request: &NewOrderRequest) -> Option<String> {
         if self.order_id_mapping.contains_key(&request.client_order_id) {
+            return Some("duplicate client order id".to_string());
+        }
+
+        let (price_required, trigger_price_required) = match request.r#type {
+            OrderType::MARKET => (false, false),
+            OrderType::LIMIT => (true, false),
+            OrderType::STOP => (false, true),
+            OrderType::STOP_LIMIT => (true, true),
+            _ => return Some(format!("unsupported order type: {:?}", request.r#type)),
+        };
+
+        if request.time_in_force == TimeInForce::GTX && !price_required {
+            return Some("post only is supported for limit orders only".to_string());
+        }
+
+        if price_required && request.price.is_none() {
+            return Some("price is required".to_string());
+        }
+
+        if trigger_price_required && request.trigger_price.is_none() {
+            return Some("trigger price is required".to_string());
+        }
+
+        None
+    }
+
+    fn on_new_order_request(&mut self, request: &NewOrderRequest, ts: Timestamp) {
+        let exchange_ts = ts + self.sample_internal_latency();
+        let received_ts = self.user_stream_timestamp(exchange_ts);
+        if let Some(reason) = self.validate_new_order_request(request) {
             let order_rejected = NewOrderRejected {
                 event_id: self.next_public_event_id(),
                 request_id: Some(request.request_id.clone()),
-                exchange_timestamp: ts + self.internal_latency,
-                timestamp: ts + self.internal_latency + self.wire_latency,
+                exchange_timestamp: exchange_ts,
+                timestamp: received_ts,
                 client_order_id: request.client_order_id.clone(),
-                reason: "duplicate client order id".to_string(),
+                reason,
                 exchange: request.exchange.clone(),
                 symbol: request.symbol.clone(),
             };
@@ -300,12 +1295,11 @@ impl SimBroker {
         self.last_exchange_order_id += 1;
         let exchange_order_id = self.last_exchange_order_id;
         let exchange_order_id_str = self.last_exchange_order_id.to_string();
-        let exchange_ts = ts + self.internal_latency;
         let order_accepted = NewOrderAccepted {
             event_id: self.next_public_event_id(),
             request_id: Some(request.request_id.clone()),
             exchange_timestamp: exchange_ts,
-            timestamp: exchange_ts + self.wire_latency,
+            timestamp: received_ts,
             client_order_id: request.client_order_id.to_string(),
             exchange_order_id: exchange_order_id_str.clone(),
             exchange: request.exchange.clone(),
@@ -317,7 +1311,7 @@ impl SimBroker {
             event_id: self.next_public_event_id(),
             exchange: request.exchange.clone(),
             exchange_timestamp: exchange_ts,
-            timestamp: exchange_ts + self.wire_latency,
+            timestamp: received_ts,
             symbol: request.symbol.clone(),
             side: request.side.clone(),
             client_order_id: Some(request.client_order_id.clone()),
@@ -327,13 +1321,15 @@ impl SimBroker {
             original_qty: request.quantity,
             original_price: request.price,
             average_price: None,
-            stop_price: None,
+            stop_price: request.trigger_price,
             execution_type: ExecutionType::NEW,
             order_status: OrderStatus::NEW,
             last_filled_qty: None,
             accumulated_filled_qty: None,
             last_filled_price: None,
             last_trade_time: None,
+            commission: None,
+            commission_asset: None,
         };
 
         self.add_generated_event(Event::UDSOrderUpdate(order_update));
@@ -356,18 +1352,46 @@ impl SimBroker {
             .insert(self.last_generated_event_id, event);
     }
 
+    fn resolve_order_id(
+        &self,
+        client_order_id: Option<&ClientOrderId>,
+        exchange_order_id: Option<&ExchangeOrderId>,
+    ) -> Result<InternalID, &'static str> {
+        let internal_id = match (exchange_order_id, client_order_id) {
+            (Some(exchange_order_id), _) => match exchange_order_id.parse::<InternalID>() {
+                Ok(val) => val,
+                Err(_) => return Err("invalid exchange order id"),
+            },
+            (None, Some(client_order_id)) => match self.order_id_mapping.get(client_order_id) {
+                Some(val) => *val,
+                None => return Err("order not found"),
+            },
+            (None, None) => return Err("order id is not set"),
+        };
+        if self.open_orders.contains_key(&internal_id) {
+            Ok(internal_id)
+        } else {
+            Err("order not found")
+        }
+    }
+
     fn on_cancel_order_requests(&mut self, request: &CancelOrderRequest, ts: Timestamp) {
-        let exchange_order_id = match request.exchange_order_id.parse::<InternalID>() {
+        let exchange_ts = ts + self.sample_internal_latency();
+        let received_ts = self.user_stream_timestamp(exchange_ts);
+        let exchange_order_id = match self.resolve_order_id(
+            request.client_order_id.as_ref(),
+            request.exchange_order_id.as_ref(),
+        ) {
             Ok(val) => val,
-            Err(_) => {
+            Err(reason) => {
                 let cancel_rejected = CancelOrderRejected {
                     event_id: self.next_public_event_id(),
                     request_id: Some(request.request_id.clone()),
-                    timestamp: ts + self.internal_latency + self.wire_latency,
-                    exchange_timestamp: ts + self.internal_latency,
+                    timestamp: received_ts,
+                    exchange_timestamp: exchange_ts,
                     client_order_id: request.client_order_id.clone(),
-                    exchange_order_id: Some(request.exchange_order_id.clone()),
-                    reason: "invalid exchange order id".to_string(),
+                    exchange_order_id: request.exchange_order_id.clone(),
+                    reason: reason.to_string(),
                     exchange: request.exchange.clone(),
                     symbol: request.symbol.clone(),
                 };
@@ -375,51 +1399,193 @@ impl SimBroker {
                 return;
             }
         };
+        let client_order_id = self.open_orders[&exchange_order_id].client_order_id.clone();
 
-        let mut order = match self.open_orders.remove(&exchange_order_id) {
-            Some(order) => order,
-            None => {
-                let cancel_rejected = CancelOrderRejected {
-                    event_id: self.next_public_event_id(),
-                    request_id: Some(request.request_id.clone()),
-                    timestamp: ts + self.internal_latency + self.wire_latency,
-                    exchange_timestamp: ts + self.internal_latency,
-                    client_order_id: request.client_order_id.clone(),
-                    exchange_order_id: Some(request.exchange_order_id.clone()),
-                    reason: "order not found".to_string(),
-                    exchange: request.exchange.clone(),
-                    symbol: request.symbol.clone(),
-                };
-                self.add_generated_event(Event::ResponseCancelOrderRejected(cancel_rejected));
-                return;
-            }
+        let exchange_order_id_str = exchange_order_id.to_string();
+        let cancel_accepted = CancelOrderAccepted {
+            event_id: self.next_public_event_id(),
+            request_id: Some(request.request_id.clone()),
+            timestamp: received_ts,
+            exchange_timestamp: exchange_ts,
+            client_order_id,
+            exchange_order_id: exchange_order_id_str,
+            exchange: request.exchange.clone(),
+            symbol: request.symbol.clone(),
         };
+        self.add_generated_event(Event::ResponseCancelOrderAccepted(cancel_accepted));
+
+        self.cancel_open_order(exchange_order_id, ts, exchange_ts, received_ts);
+    }
 
+    fn on_cancel_all_request(&mut self, request: &CancelAllRequest, ts: Timestamp) {
+        let mut order_ids: Vec<InternalID> = self
+            .open_orders
+            .iter()
+            .filter(|(_, order)| match &request.symbol {
+                Some(symbol) => &order.symbol == symbol,
+                None => true,
+            })
+            .map(|(&k, _)| k)
+            .collect();
+        order_ids.sort_unstable();
+        debug!(
+            "cancel all request: {:?}. orders: {:?}",
+            request, &order_ids
+        );
+
+        let exchange_ts = ts + self.sample_internal_latency();
+        let received_ts = self.user_stream_timestamp(exchange_ts);
+        for exchange_order_id in order_ids {
+            self.cancel_open_order(exchange_order_id, ts, exchange_ts, received_ts);
+        }
+    }
+
+    fn cancel_open_order(
+        &mut self,
+        exchange_order_id: InternalID,
+        ts: Timestamp,
+        exchange_ts: Timestamp,
+        received_ts: Timestamp,
+    ) {
+        let mut order = self.open_orders.remove(&exchange_order_id).unwrap();
         debug!("delete order: {:?}", &order);
+        self.queue_positions.remove(&exchange_order_id);
+        self.resting_orders.remove(&exchange_order_id);
 
         if let Err(err) = &order.cancel(ts) {
             panic!("failed to cancel order: {:?}", err)
         };
 
-        let exchange_order_id_str = exchange_order_id.to_string();
+        let order_update = OrderUpdate {
+            event_id: self.next_public_event_id(),
+            exchange: order.exchange.clone(),
+            timestamp: received_ts,
+            exchange_timestamp: exchange_ts,
+            symbol: order.symbol.clone(),
+            side: order.side.clone(),
+            client_order_id: Some(order.client_order_id.clone()),
+            exchange_order_id: Some(exchange_order_id.to_string()),
+            order_type: Some(order.r#type.clone()),
+            time_in_force: Some(order.time_in_force.clone()),
+            original_qty: order.quantity,
+            original_price: order.price,
+            average_price: order.avg_fill_price,
+            stop_price: order.trigger_price,
+            execution_type: ExecutionType::CANCELED,
+            order_status: OrderStatus::CANCELED,
+            last_filled_qty: None,
+            accumulated_filled_qty: order.filled_quantity,
+            last_filled_price: None,
+            last_trade_time: None,
+            commission: None,
+            commission_asset: None,
+        };
+        self.add_generated_event(Event::UDSOrderUpdate(order_update));
 
-        let cancel_accepted = CancelOrderAccepted {
+        self.done_orders.insert(exchange_order_id, order);
+    }
+
+    fn reject_amend_order_request(
+        &mut self,
+        request: &AmendOrderRequest,
+        exchange_ts: Timestamp,
+        received_ts: Timestamp,
+        reason: &str,
+    ) {
+        let amend_rejected = AmendOrderRejected {
             event_id: self.next_public_event_id(),
             request_id: Some(request.request_id.clone()),
-            timestamp: ts + self.internal_latency + self.wire_latency,
-            exchange_timestamp: ts + self.internal_latency,
+            timestamp: received_ts,
+            exchange_timestamp: exchange_ts,
+            client_order_id: request.client_order_id.clone(),
+            exchange_order_id: Some(request.exchange_order_id.clone()),
+            reason: reason.to_string(),
+            exchange: request.exchange.clone(),
+            symbol: request.symbol.clone(),
+        };
+        self.add_generated_event(Event::ResponseAmendOrderRejected(amend_rejected));
+    }
+
+    fn on_amend_order_request(&mut self, request: &AmendOrderRequest, ts: Timestamp) {
+        let exchange_ts = ts + self.sample_internal_latency();
+        let received_ts = self.user_stream_timestamp(exchange_ts);
+        let exchange_order_id = match request.exchange_order_id.parse::<InternalID>() {
+            Ok(val) => val,
+            Err(_) => {
+                self.reject_amend_order_request(
+                    request,
+                    exchange_ts,
+                    received_ts,
+                    "invalid exchange order id",
+                );
+                return;
+            }
+        };
+
+        let order = match self.open_orders.get(&exchange_order_id) {
+            Some(order) => order,
+            None => {
+                self.reject_amend_order_request(
+                    request,
+                    exchange_ts,
+                    received_ts,
+                    "order not found",
+                );
+                return;
+            }
+        };
+
+        if request.new_price.is_none() && request.new_quantity.is_none() {
+            self.reject_amend_order_request(request, exchange_ts, received_ts, "nothing to amend");
+            return;
+        }
+
+        if request.new_price.is_some() && order.price.is_none() {
+            self.reject_amend_order_request(
+                request,
+                exchange_ts,
+                received_ts,
+                "price can't be amended for order without price",
+            );
+            return;
+        }
+
+        if let Some(new_quantity) = request.new_quantity {
+            if new_quantity <= order.filled_quantity.unwrap_or(0.0) {
+                self.reject_amend_order_request(
+                    request,
+                    exchange_ts,
+                    received_ts,
+                    "new quantity should be greater than filled quantity",
+                );
+                return;
+            }
+        }
+
+        let accepted_event_id = self.next_public_event_id();
+        let update_event_id = self.next_public_event_id();
+        let order = self.open_orders.get_mut(&exchange_order_id).unwrap();
+        let loses_priority = order.amend(request.new_price, request.new_quantity, ts);
+        debug!("amend order: {:?}", &order);
+
+        let exchange_order_id_str = exchange_order_id.to_string();
+        let amend_accepted = AmendOrderAccepted {
+            event_id: accepted_event_id,
+            request_id: Some(request.request_id.clone()),
+            timestamp: received_ts,
+            exchange_timestamp: exchange_ts,
             client_order_id: order.client_order_id.clone(),
             exchange_order_id: exchange_order_id_str.clone(),
+            price: order.price,
+            quantity: order.quantity,
             exchange: request.exchange.clone(),
             symbol: request.symbol.clone(),
         };
-
-        self.add_generated_event(Event::ResponseCancelOrderAccepted(cancel_accepted));
         let order_update = OrderUpdate {
-            event_id: self.next_public_event_id(),
+            event_id: update_event_id,
             exchange: request.exchange.clone(),
-            timestamp: ts + self.internal_latency + self.wire_latency,
-            exchange_timestamp: ts + self.internal_latency,
+            timestamp: received_ts,
+            exchange_timestamp: exchange_ts,
             symbol: order.symbol.clone(),
             side: order.side.clone(),
             client_order_id: Some(order.client_order_id.clone()),
@@ -430,22 +1596,30 @@ impl SimBroker {
             original_price: order.price,
             average_price: order.avg_fill_price,
             stop_price: order.trigger_price,
-            execution_type: ExecutionType::CANCELED,
-            order_status: OrderStatus::CANCELED,
+            execution_type: ExecutionType::AMENDMENT,
+            order_status: order.status.clone(),
             last_filled_qty: None,
             accumulated_filled_qty: order.filled_quantity,
             last_filled_price: None,
             last_trade_time: None,
+            commission: None,
+            commission_asset: None,
         };
-        self.add_generated_event(Event::UDSOrderUpdate(order_update));
 
-        self.done_orders.insert(exchange_order_id, order);
+        if loses_priority {
+            // amended order goes to the end of queue and passes arrival checks again
+            self.queue_positions.remove(&exchange_order_id);
+            self.resting_orders.remove(&exchange_order_id);
+        }
+
+        self.add_generated_event(Event::ResponseAmendOrderAccepted(amend_accepted));
+        self.add_generated_event(Event::UDSOrderUpdate(order_update));
     }
 
     fn get_generated_events(&mut self) -> Vec<Event> {
         let mut events = vec![];
 
-        let event_ids: Vec<u64> = self.generated_events.keys().map(|&k| k).collect();
+        let event_ids: Vec<u64> = self.generated_events.keys().copied().collect();
         for event_id in event_ids {
             let event = match self.generated_events.remove(&event_id) {
                 Some(event) => event,
@@ -467,8 +1641,12 @@ impl SimBroker {
                 ts, &exchange_request
             );
             self.last_request_id += 1;
+            // requests are sent through one connection, so they can't overtake each other
+            let ack_timestamp = (exchange_request.creation_ts() + self.sample_wire_latency())
+                .max(self.last_request_ack_ts);
+            self.last_request_ack_ts = ack_timestamp;
             let wrapped_request = SimBrokerExchangeRequest {
-                ack_timestamp: exchange_request.creation_ts() + self.wire_latency,
+                ack_timestamp,
                 request_id: self.last_request_id,
                 exchange_request,
             };
@@ -493,23 +1671,25 @@ impl SimulatedBroker for SimBroker {
         self.get_generated_events()
     }
 
+    /// Md event is stamped with received timestamp, unless sim environment already did it
     fn on_new_market_data(&mut self, md: &MarketDataEvent) -> Vec<Event> {
         let md_ts = md.exchange_timestamp();
         self.last_ts = md_ts;
-        let mut md_forward = md.clone();
-        md_forward.set_timestamp(self.estimate_market_data_timestamp(md));
-        self.add_generated_event(md_forward.into());
+        let mut received_md = md.clone();
+        if self.stamped_md_events > 0 {
+            self.stamped_md_events -= 1;
+        } else {
+            received_md.set_timestamp(self.md_received_timestamp(md));
+        }
+        self.add_generated_event(received_md.into());
 
         self.process_requests_on_new_ts(md_ts);
         self.update_orders_on_md(md);
         self.get_generated_events()
     }
 
-    fn estimate_market_data_timestamp(&self, md: &MarketDataEvent) -> Timestamp {
-        md.exchange_timestamp() + self.wire_latency
-    }
-
-    fn wire_latency(&self) -> Timestamp {
-        self.wire_latency
+    fn sample_market_data_timestamp(&mut self, md: &MarketDataEvent) -> Timestamp {
+        self.stamped_md_events += 1;
+        self.md_received_timestamp(md)
     }
 }
@@ -0,0 +1,380 @@
+use super::environment::SimulatedTradingMarketDataProvider;
+use crate::core::market_data::{MarketDataEvent, Quote, Trade};
+use crate::core::types::{Exchange, Symbol, Timestamp};
+use csv::{Reader, ReaderBuilder, StringRecord};
+use log::error;
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub enum CsvProviderError {
+    Csv(csv::Error),
+    MissingColumn(String),
+    MissingValue {
+        line: u64,
+        column: String,
+    },
+    InvalidValue {
+        line: u64,
+        column: String,
+        value: String,
+    },
+}
+
+impl From<csv::Error> for CsvProviderError {
+    fn from(err: csv::Error) -> Self {
+        Self::Csv(err)
+    }
+}
+
+impl CsvProviderError {
+    fn is_io(&self) -> bool {
+        matches!(self, Self::Csv(err) if matches!(err.kind(), csv::ErrorKind::Io(_)))
+    }
+}
+
+type Result<T> = std::result::Result<T, CsvProviderError>;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TimestampUnit {
+    Seconds,
+    #[default]
+    Milliseconds,
+    Microseconds,
+    Nanoseconds,
+}
+
+impl TimestampUnit {
+    /// Converts integer or fractional timestamp to milliseconds.
+    /// None if value is not a non-negative number or doesn't fit into timestamp
+    pub fn to_millis(&self, value: &str) -> Option<Timestamp> {
+        if let Ok(value) = value.parse::<u64>() {
+            return match self {
+                Self::Seconds => value.checked_mul(1_000),
+                Self::Milliseconds => Some(value),
+                Self::Microseconds => Some(value / 1_000),
+                Self::Nanoseconds => Some(value / 1_000_000),
+            };
+        }
+        let value = value.parse::<f64>().ok()?;
+        if !value.is_finite() || value < 0.0 {
+            return None;
+        }
+        let millis = match self {
+            Self::Seconds => value * 1_000.0,
+            Self::Milliseconds => value,
+            Self::Microseconds => value / 1_000.0,
+            Self::Nanoseconds => value / 1_000_000.0,
+        };
+        if millis >= Timestamp::MAX as f64 {
+            return None;
+        }
+        Some(millis as Timestamp)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvRecordType {
+    Trade,
+    Quote,
+}
+
+/// Event fields which can be read from CSV column
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CsvField {
+    EventId,
+    Symbol,
+    Exchange,
+    Price,
+    Size,
+    Bid,
+    Ask,
+    BidSize,
+    AskSize,
+    ExchangeTimestamp,
+    ReceivedTimestamp,
+}
+
+impl CsvField {
+    fn default_column(&self) -> &'static str {
+        match self {
+            Self::EventId => "event_id",
+            Self::Symbol => "symbol",
+            Self::Exchange => "exchange",
+            Self::Price => "last_price",
+            Self::Size => "last_size",
+            Self::Bid => "bid",
+            Self::Ask => "ask",
+            Self::BidSize => "bid_size",
+            Self::AskSize => "ask_size",
+            Self::ExchangeTimestamp => "exchange_timestamp",
+            Self::ReceivedTimestamp => "received_timestamp",
+        }
+    }
+
+    fn is_required(&self, record_type: CsvRecordType) -> bool {
+        matches!(
+            (record_type, self),
+            (_, Self::ExchangeTimestamp)
+                | (CsvRecordType::Trade, Self::Price | Self::Size)
+                | (CsvRecordType::Quote, Self::Bid | Self::Ask)
+        )
+    }
+}
+
+/// Columns are matched by header name. By default column is named as event field,
+/// e.g. `last_price` for trade price
+#[derive(Debug, Clone)]
+pub struct CsvProviderConfig {
+    record_type: CsvRecordType,
+    columns: HashMap<CsvField, String>,
+    timestamp_unit: TimestampUnit,
+    default_exchange: Option<Exchange>,
+    default_symbol: Option<Symbol>,
+    delimiter: u8,
+    skip_invalid_rows: bool,
+}
+
+impl CsvProviderConfig {
+    pub fn new(record_type: CsvRecordType) -> Self {
+        Self {
+            record_type,
+            columns: HashMap::new(),
+            timestamp_unit: TimestampUnit::default(),
+            default_exchange: None,
+            default_symbol: None,
+            delimiter: b',',
+            skip_invalid_rows: false,
+        }
+    }
+
+    pub fn trades() -> Self {
+        Self::new(CsvRecordType::Trade)
+    }
+
+    pub fn quotes() -> Self {
+        Self::new(CsvRecordType::Quote)
+    }
+
+    pub fn with_column(mut self, field: CsvField, column: &str) -> Self {
+        self.columns.insert(field, column.to_string());
+        self
+    }
+
+    pub fn with_timestamp_unit(mut self, timestamp_unit: TimestampUnit) -> Self {
+        self.timestamp_unit = timestamp_unit;
+        self
+    }
+
+    /// Used when file has no exchange column or its value is empty
+    pub fn with_default_exchange(mut self, exchange: Exchange) -> Self {
+        self.default_exchange = Some(exchange);
+        self
+    }
+
+    /// Used when file has no symbol column or its value is empty
+    pub fn with_default_symbol(mut self, symbol: Symbol) -> Self {
+        self.default_symbol = Some(symbol);
+        self
+    }
+
+    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Invalid rows are logged and skipped instead of ending the stream.
+    /// I/O errors always end the stream
+    pub fn with_skip_invalid_rows(mut self, skip_invalid_rows: bool) -> Self {
+        self.skip_invalid_rows = skip_invalid_rows;
+        self
+    }
+
+    fn column(&self, field: CsvField) -> &str {
+        match self.columns.get(&field) {
+            Some(val) => val.as_str(),
+            None => field.default_column(),
+        }
+    }
+}
+
+/// Streams trades or quotes from CSV file with header row, one record at a time
+#[derive(Debug)]
+pub struct CsvMarketDataProvider {
+    path: PathBuf,
+    reader: Reader<File>,
+    record: StringRecord,
+    config: CsvProviderConfig,
+    column_indices: HashMap<CsvField, usize>,
+    exhausted: bool,
+}
+
+impl CsvMarketDataProvider {
+    pub fn new<P: AsRef<Path>>(path: P, config: CsvProviderConfig) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut reader = ReaderBuilder::new()
+            .delimiter(config.delimiter)
+            .trim(csv::Trim::All)
+            .from_path(&path)?;
+
+        let headers = reader.headers()?.clone();
+        let mut column_indices = HashMap::new();
+        let fields = [
+            CsvField::Exchange,
+            CsvField::Symbol,
+            CsvField::EventId,
+            CsvField::Price,
+            CsvField::Size,
+            CsvField::Bid,
+            CsvField::Ask,
+            CsvField::BidSize,
+            CsvField::AskSize,
+            CsvField::ExchangeTimestamp,
+            CsvField::ReceivedTimestamp,
+        ];
+        for field in fields {
+            let column = config.column(field);
+            match headers.iter().position(|header| header == column) {
+                Some(idx) => {
+                    column_indices.insert(field, idx);
+                }
+                None => {
+                    let has_default = match field {
+                        CsvField::Exchange => config.default_exchange.is_some(),
+                        CsvField::Symbol => config.default_symbol.is_some(),
+                        _ => !field.is_required(config.record_type),
+                    };
+                    if !has_default {
+                        return Err(CsvProviderError::MissingColumn(column.to_string()));
+                    }
+                }
+            }
+        }
+
+        Ok(Self {
+            path,
+            reader,
+            record: StringRecord::new(),
+            config,
+            column_indices,
+            exhausted: false,
+        })
+    }
+
+    /// Reads next event. Ok(None) is returned at the end of file
+    pub fn read_event(&mut self) -> Result<Option<MarketDataEvent>> {
+        if !self.reader.read_record(&mut self.record)? {
+            return Ok(None);
+        }
+
+        let exchange_timestamp = self.timestamp(CsvField::ExchangeTimestamp)?.unwrap();
+        let received_timestamp = self
+            .timestamp(CsvField::ReceivedTimestamp)?
+            .unwrap_or(exchange_timestamp);
+        let exchange = self.string_or(CsvField::Exchange, &self.config.default_exchange)?;
+        let symbol = self.string_or(CsvField::Symbol, &self.config.default_symbol)?;
+        let event_id = self.value(CsvField::EventId).map(|val| val.to_string());
+
+        let event = match self.config.record_type {
+            CsvRecordType::Trade => MarketDataEvent::NewMarketTrade(Trade {
+                event_id,
+                symbol,
+                exchange,
+                last_price: self.required_number(CsvField::Price)?,
+                last_size: self.required_number(CsvField::Size)?,
+                exchange_timestamp,
+                received_timestamp,
+            }),
+            CsvRecordType::Quote => MarketDataEvent::NewQuote(Quote {
+                event_id,
+                symbol,
+                exchange,
+                bid: self.required_number(CsvField::Bid)?,
+                ask: self.required_number(CsvField::Ask)?,
+                bid_size: self.number(CsvField::BidSize)?,
+                ask_size: self.number(CsvField::AskSize)?,
+                exchange_timestamp,
+                received_timestamp,
+            }),
+        };
+        Ok(Some(event))
+    }
+
+    fn line(&self) -> u64 {
+        self.record.position().map_or(0, |position| position.line())
+    }
+
+    /// Non empty value of field column
+    fn value(&self, field: CsvField) -> Option<&str> {
+        let idx = self.column_indices.get(&field)?;
+        self.record.get(*idx).filter(|val| !val.is_empty())
+    }
+
+    fn missing_value(&self, field: CsvField) -> CsvProviderError {
+        CsvProviderError::MissingValue {
+            line: self.line(),
+            column: self.config.column(field).to_string(),
+        }
+    }
+
+    fn invalid_value(&self, field: CsvField, value: &str) -> CsvProviderError {
+        CsvProviderError::InvalidValue {
+            line: self.line(),
+            column: self.config.column(field).to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    fn string_or(&self, field: CsvField, default: &Option<String>) -> Result<String> {
+        match (self.value(field), default) {
+            (Some(val), _) => Ok(val.to_string()),
+            (None, Some(default)) => Ok(default.clone()),
+            (None, None) => Err(self.missing_value(field)),
+        }
+    }
+
+    fn number(&self, field: CsvField) -> Result<Option<f64>> {
+        match self.value(field) {
+            Some(val) => match val.parse::<f64>() {
+                Ok(number) => Ok(Some(number)),
+                Err(_) => Err(self.invalid_value(field, val)),
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn required_number(&self, field: CsvField) -> Result<f64> {
+        self.number(field)?.ok_or_else(|| self.missing_value(field))
+    }
+
+    fn timestamp(&self, field: CsvField) -> Result<Option<Timestamp>> {
+        match self.value(field) {
+            Some(val) => match self.config.timestamp_unit.to_millis(val) {
+                Some(ts) => Ok(Some(ts)),
+                None => Err(self.invalid_value(field, val)),
+            },
+            None if field.is_required(self.config.record_type) => Err(self.missing_value(field)),
+            None => Ok(None),
+        }
+    }
+}
+
+impl SimulatedTradingMarketDataProvider for CsvMarketDataProvider {
+    fn next_event(&mut self) -> Option<MarketDataEvent> {
+        while !self.exhausted {
+            match self.read_event() {
+                Ok(event) => {
+                    self.exhausted = event.is_none();
+                    return event;
+                }
+                Err(err) => {
+                    error!("failed to read {}: {:?}", self.path.display(), err);
+                    // I/O error is not bound to a row, so reading further can't make progress
+                    self.exhausted = err.is_io() || !self.config.skip_invalid_rows;
+                }
+            }
+        }
+        None
+    }
+}
@@ -1,8 +1,12 @@
 use crate::core::events::Event;
 use crate::core::market_data::MarketDataEvent;
 
+use crate::core::bar_aggregator::BarAggregator;
+use crate::core::clock::{Clock, SimClock};
 use crate::core::event_loop::EventProvider;
+use crate::core::timer::{TimerQueue, TimerRequest};
 use crate::core::types::{Exchange, Timestamp};
+use crossbeam_channel::Receiver;
 use log::warn;
 use std::collections::HashMap;
 
@@ -21,8 +25,24 @@ pub trait SimulatedBroker {
     fn exchange(&self) -> Exchange;
     fn on_new_timestamp(&mut self, ts: Timestamp) -> Vec<Event>;
     fn on_new_market_data(&mut self, md: &MarketDataEvent) -> Vec<Event>;
-    fn estimate_market_data_timestamp(&self, md: &MarketDataEvent) -> Timestamp;
-    fn wire_latency(&self) -> Timestamp;
+
+    /// Samples timestamp when md event is received. Called exactly once per md event.
+    /// Defaults to `estimate_market_data_timestamp` for brokers with deterministic latency
+    #[allow(deprecated)]
+    fn sample_market_data_timestamp(&mut self, md: &MarketDataEvent) -> Timestamp {
+        self.estimate_market_data_timestamp(md)
+    }
+
+    #[deprecated(note = "latency can be stochastic, use `sample_market_data_timestamp`")]
+    #[allow(deprecated)]
+    fn estimate_market_data_timestamp(&self, md: &MarketDataEvent) -> Timestamp {
+        md.exchange_timestamp() + self.wire_latency()
+    }
+
+    #[deprecated(note = "sim environment doesn't use max wire latency anymore")]
+    fn wire_latency(&self) -> Timestamp {
+        0
+    }
 }
 
 pub struct SimulatedEnvironment<T: SimulatedTradingMarketDataProvider, B: SimulatedBroker> {
@@ -33,7 +53,13 @@ pub struct SimulatedEnvironment<T: SimulatedTradingMarketDataProvider, B: Simula
     default_latency: u64,
     no_more_md: bool,
     md_event_buffer: Vec<MarketDataEvent>,
-    max_md_wire_latency: Timestamp,
+    md_provider_exhausted: bool,
+    timer_requests_receiver: Option<Receiver<TimerRequest>>,
+    timers: TimerQueue,
+    clock: SimClock,
+    // timestamp brokers were advanced to before firing timer
+    brokers_synced_ts: Option<Timestamp>,
+    bar_aggregators: Vec<BarAggregator>,
 }
 
 impl<T: SimulatedTradingMarketDataProvider, B: SimulatedBroker> SimulatedEnvironment<T, B> {
@@ -47,7 +73,12 @@ impl<T: SimulatedTradingMarketDataProvider, B: SimulatedBroker> SimulatedEnviron
             no_more_md: false,
             default_latency: default_latency.unwrap_or(0),
             md_event_buffer: vec![],
-            max_md_wire_latency: 0,
+            md_provider_exhausted: false,
+            timer_requests_receiver: None,
+            timers: TimerQueue::new(),
+            clock: SimClock::new(),
+            brokers_synced_ts: None,
+            bar_aggregators: vec![],
         }
     }
 
@@ -56,17 +87,86 @@ impl<T: SimulatedTradingMarketDataProvider, B: SimulatedBroker> SimulatedEnviron
         if self.brokers.contains_key(&exchange) {
             return Err(SimTradingError::BrokerAlreadyExists);
         };
-        let broker_wire_latency = broker.wire_latency();
-        if broker_wire_latency > self.max_md_wire_latency {
-            self.max_md_wire_latency = broker_wire_latency;
-        }
         self.brokers.insert(exchange, broker);
         Ok(())
     }
 
-    fn md_event_expected_received_ts(&self, md: &MarketDataEvent) -> Timestamp {
-        match self.brokers.get(md.exchange().as_str()) {
-            Some(broker) => broker.estimate_market_data_timestamp(md),
+    /// Bars are built from trades of md provider and delivered as market data:
+    /// time bar is received after its interval end, other bars right after the trade completing them
+    pub fn add_bar_aggregator(&mut self, aggregator: BarAggregator) {
+        self.bar_aggregators.push(aggregator);
+    }
+
+    pub fn brokers(&self) -> &HashMap<Exchange, B> {
+        &self.brokers
+    }
+
+    /// Clock which follows timestamps of events returned by environment
+    pub fn clock(&self) -> SimClock {
+        self.clock.clone()
+    }
+
+    pub fn set_timer_requests_receiver(&mut self, receiver: Receiver<TimerRequest>) {
+        self.timer_requests_receiver = Some(receiver);
+    }
+
+    fn receive_timer_requests(&mut self) {
+        if let Some(receiver) = &self.timer_requests_receiver {
+            while let Ok(request) = receiver.try_recv() {
+                self.timers.on_request(request, self.clock.now());
+            }
+        }
+    }
+
+    /// Returns true if timer is due before any buffered broker or pending md event.
+    /// Brokers are advanced to timer timestamp first, so events generated before it go first
+    fn is_timer_due(&mut self) -> bool {
+        let timer_ts = match self.timers.next_fire_ts() {
+            Some(val) => val,
+            None => return false,
+        };
+        let next_event_ts = self
+            .broker_events_buffer
+            .first()
+            .map(|e| e.timestamp())
+            .into_iter()
+            .chain(self.pending_md_event.as_ref().map(|e| e.timestamp()))
+            .min();
+        if let Some(next_event_ts) = next_event_ts {
+            if next_event_ts < timer_ts {
+                return false;
+            }
+        }
+
+        if self.brokers_synced_ts != Some(timer_ts) {
+            self.brokers_synced_ts = Some(timer_ts);
+            let broker_ts = match &self.pending_md_event {
+                Some(md) => timer_ts.min(md.exchange_timestamp()),
+                None => timer_ts,
+            };
+            let mut new_events = false;
+            for (_, broker) in self.brokers.iter_mut() {
+                let events = broker.on_new_timestamp(broker_ts);
+                new_events |= !events.is_empty();
+                self.broker_events_buffer.extend(events);
+            }
+            if new_events {
+                self.broker_events_buffer.sort_by_key(|a| a.timestamp());
+                return self.broker_events_buffer[0].timestamp() >= timer_ts;
+            }
+        }
+        true
+    }
+
+    fn pop_event(&mut self) -> Event {
+        let event = self.broker_events_buffer.remove(0); // TODO alex optimize
+        self.clock.advance_to(event.timestamp());
+        event
+    }
+
+    fn md_event_expected_received_ts(&mut self, md: &MarketDataEvent) -> Timestamp {
+        match self.brokers.get_mut(md.exchange().as_str()) {
+            Some(broker) => broker.sample_market_data_timestamp(md),
             None => {
                 warn!("broker not found for md event exchange: {}", md.exchange());
                 md.exchange_timestamp() + self.default_latency
@@ -74,50 +174,95 @@ impl<T: SimulatedTradingMarketDataProvider, B: SimulatedBroker> SimulatedEnviron
         }
     }
 
+    fn read_md_event_to_buffer(&mut self) {
+        let event = match self.md_provider.next_event() {
+            Some(val) => val,
+            None => {
+                self.md_provider_exhausted = true;
+                let mut bars: Vec<MarketDataEvent> = self
+                    .bar_aggregators
+                    .iter_mut()
+                    .flat_map(|aggregator| aggregator.flush())
+                    .map(MarketDataEvent::NewBar)
+                    .collect();
+                bars.sort_by_key(|bar| bar.exchange_timestamp());
+                for mut bar in bars {
+                    let received_ts = self.md_event_expected_received_ts(&bar);
+                    bar.set_timestamp(received_ts);
+                    self.md_event_buffer.push(bar);
+                }
+                return;
+            }
+        };
+
+        let mut events = vec![];
+        for aggregator in self.bar_aggregators.iter_mut() {
+            let bars = aggregator.on_timestamp(event.exchange_timestamp());
+            events.extend(bars.into_iter().map(MarketDataEvent::NewBar));
+        }
+        let completed_bars: Vec<MarketDataEvent> = match &event {
+            MarketDataEvent::NewMarketTrade(trade) => self
+                .bar_aggregators
+                .iter_mut()
+                .filter_map(|aggregator| aggregator.on_trade(trade))
+                .map(MarketDataEvent::NewBar)
+                .collect(),
+            _ => vec![],
+        };
+        events.push(event);
+        events.extend(completed_bars);
+
+        for mut event in events {
+            let received_ts = self.md_event_expected_received_ts(&event);
+            event.set_timestamp(received_ts);
+            self.md_event_buffer.push(event);
+        }
+    }
+
     fn update_pending_md(&mut self) {
         if self.pending_md_event.is_some() {
             return;
         }
 
         if self.md_event_buffer.is_empty() {
-            if self.no_more_md {
+            if self.md_provider_exhausted {
+                self.no_more_md = true;
                 return;
             }
-            let event = self.md_provider.next_event();
-            if event.is_none() {
+            self.read_md_event_to_buffer();
+            if self.md_event_buffer.is_empty() {
                 self.no_more_md = true;
                 return;
             }
-            let event = event.unwrap();
-            self.md_event_buffer.push(event);
         }
 
-        // expect that earliest event has max wire latency and min latency is 0
-        // read all events with exchange ts <= earliest event + max latency
-        // we expect that md is sorted by exchange ts, so first event in buffer should always have min exchange ts
-        let max_exchange_ts_to_read =
-            self.md_event_buffer[0].exchange_timestamp() + self.max_md_wire_latency;
+        // we expect that md is sorted by exchange ts (use MergedMarketDataProvider for several
+        // sorted sources) and latency is not negative.
+        // so once we read event with exchange ts > earliest received ts in buffer,
+        // none of next events can be received before buffered one
+        let mut earliest_received_event_idx = 0;
+        let mut earliest_receive_ts = self.md_event_buffer[0].timestamp();
+        for (i, event) in self.md_event_buffer.iter().enumerate() {
+            if event.timestamp() < earliest_receive_ts {
+                earliest_receive_ts = event.timestamp();
+                earliest_received_event_idx = i;
+            }
+        }
 
         loop {
-            let event = self.md_provider.next_event();
-            if event.is_none() {
+            let last_event = &self.md_event_buffer[self.md_event_buffer.len() - 1];
+            if self.md_provider_exhausted || last_event.exchange_timestamp() > earliest_receive_ts {
                 break;
             }
-            let event = event.unwrap();
-            let event_exchange_ts = event.exchange_timestamp();
-            self.md_event_buffer.push(event);
-            if event_exchange_ts > max_exchange_ts_to_read {
-                break;
-            }
-        }
-
-        let mut earliest_received_event_idx = 0;
-        let mut earliest_receive_ts = self.md_event_expected_received_ts(&self.md_event_buffer[0]);
-        for i in 0..self.md_event_buffer.len() {
-            let expected_ts = self.md_event_expected_received_ts(&self.md_event_buffer[i]);
-            if expected_ts < earliest_receive_ts {
-                earliest_receive_ts = expected_ts;
-                earliest_received_event_idx = i;
+            // one read can add several events when bars are closed by it
+            let first_new_idx = self.md_event_buffer.len();
+            self.read_md_event_to_buffer();
+            for idx in first_new_idx..self.md_event_buffer.len() {
+                let received_ts = self.md_event_buffer[idx].timestamp();
+                if received_ts < earliest_receive_ts {
+                    earliest_receive_ts = received_ts;
+                    earliest_received_event_idx = idx;
+                }
             }
         }
 
@@ -165,34 +310,37 @@ impl<T: SimulatedTradingMarketDataProvider, B: SimulatedBroker> EventProvider
 {
     fn next_event(&mut self) -> Option<Event> {
         loop {
+            self.receive_timer_requests();
             self.update_pending_md();
 
+            // sim ends with market data, timers which are still scheduled never fire
+            if self.no_more_md && self.broker_events_buffer.is_empty() {
+                return None;
+            }
+
+            if self.is_timer_due() {
+                let timer = self.timers.pop().unwrap();
+                self.clock.advance_to(timer.timestamp);
+                return Some(Event::Timer(timer));
+            }
+
             if self.broker_events_buffer.is_empty() {
-                if self.no_more_md {
-                    return None;
-                }
                 let pending_md_event = self.pending_md_event.take().unwrap();
                 self.pending_md_event = self.feed_market_data_event_to_brokers(pending_md_event);
                 continue;
             }
 
             if self.no_more_md {
-                if !self.broker_events_buffer.is_empty() {
-                    let event = self.broker_events_buffer.remove(0); // TODO alex optimize
-                    return Some(event);
-                }
-                return None;
+                return Some(self.pop_event());
             }
-            let pending_md_event = self.pending_md_event.as_ref().unwrap();
-            let expected_md_event_ts = self.md_event_expected_received_ts(pending_md_event);
+            let expected_md_event_ts = self.pending_md_event.as_ref().unwrap().timestamp();
 
             let earliest_broker_event = &self.broker_events_buffer[0];
             if earliest_broker_event.timestamp() > expected_md_event_ts {
                 let pending_md_event = self.pending_md_event.take().unwrap();
                 self.pending_md_event = self.feed_market_data_event_to_brokers(pending_md_event);
             } else {
-                let event = self.broker_events_buffer.remove(0); // TODO alex optimize
-                return Some(event);
+                return Some(self.pop_event());
             }
         }
     }
@@ -0,0 +1,40 @@
+use crate::core::types::Asset;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FeeSchedule {
+    /// Rate applied to notional of fills providing liquidity. Negative rate is a rebate
+    pub maker_rate: f64,
+    /// Rate applied to notional of fills taking liquidity. Negative rate is a rebate
+    pub taker_rate: f64,
+    /// Flat fee charged once per order on its first fill
+    pub per_order_fee: f64,
+    pub commission_asset: Option<Asset>,
+}
+
+impl FeeSchedule {
+    pub fn new(
+        maker_rate: f64,
+        taker_rate: f64,
+        per_order_fee: f64,
+        commission_asset: Option<Asset>,
+    ) -> Self {
+        Self {
+            maker_rate,
+            taker_rate,
+            per_order_fee,
+            commission_asset,
+        }
+    }
+
+    pub fn commission(&self, price: f64, quantity: f64, is_maker: bool, first_fill: bool) -> f64 {
+        let rate = match is_maker {
+            true => self.maker_rate,
+            false => self.taker_rate,
+        };
+        let per_order_fee = match first_fill {
+            true => self.per_order_fee,
+            false => 0.0,
+        };
+        price * quantity * rate + per_order_fee
+    }
+}
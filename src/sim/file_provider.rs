@@ -0,0 +1,245 @@
+use super::environment::SimulatedTradingMarketDataProvider;
+use crate::core::market_data::MarketDataEvent;
+use log::error;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub enum FileProviderError {
+    Io {
+        path: PathBuf,
+        err: std::io::Error,
+    },
+    /// Glob pattern or directory doesn't contain any file
+    NoFiles(String),
+    MsgPack {
+        path: PathBuf,
+        err: rmp_serde::decode::Error,
+    },
+    Json {
+        path: PathBuf,
+        line: usize,
+        err: serde_json::Error,
+    },
+}
+
+type Result<T> = std::result::Result<T, FileProviderError>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    /// Concatenated msgpack encoded events
+    MsgPack,
+    /// One JSON encoded event per line, empty lines are skipped
+    JsonLines,
+}
+
+/// Streams `MarketDataEvent`s from msgpack or JSON-lines files one event at a time.
+/// Files are read one after another, so events must be sorted by exchange ts across files
+#[derive(Debug)]
+pub struct FileMarketDataProvider {
+    format: FileFormat,
+    files: VecDeque<PathBuf>,
+    current_path: PathBuf,
+    reader: Option<BufReader<File>>,
+    line: usize,
+    exhausted: bool,
+}
+
+impl FileMarketDataProvider {
+    /// Files are read in given order
+    pub fn new(files: Vec<PathBuf>, format: FileFormat) -> Self {
+        Self {
+            format,
+            files: files.into(),
+            current_path: PathBuf::new(),
+            reader: None,
+            line: 0,
+            exhausted: false,
+        }
+    }
+
+    /// All files of directory sorted by name
+    pub fn from_dir<P: AsRef<Path>>(dir: P, format: FileFormat) -> Result<Self> {
+        let dir = dir.as_ref();
+        let files = Self::list_files(dir, |_| true)?;
+        if files.is_empty() {
+            return Err(FileProviderError::NoFiles(dir.display().to_string()));
+        }
+        Ok(Self::new(files, format))
+    }
+
+    /// Files matching pattern sorted by name, e.g. `data/quotes_*.msgpack`.
+    /// Wildcards `*` and `?` are supported in the file name only
+    pub fn from_glob(pattern: &str, format: FileFormat) -> Result<Self> {
+        let pattern_path = Path::new(pattern);
+        let dir = match pattern_path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let name_pattern = pattern_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let files = Self::list_files(dir, |name| wildcard_match(&name_pattern, name))?;
+        if files.is_empty() {
+            return Err(FileProviderError::NoFiles(pattern.to_string()));
+        }
+        Ok(Self::new(files, format))
+    }
+
+    /// Reads next event, moving to the next file at the end of current one.
+    /// Ok(None) is returned when all files are read
+    pub fn read_event(&mut self) -> Result<Option<MarketDataEvent>> {
+        loop {
+            let reader = match self.reader.as_mut() {
+                Some(reader) => reader,
+                None => {
+                    let path = match self.files.pop_front() {
+                        Some(path) => path,
+                        None => return Ok(None),
+                    };
+                    let file = File::open(&path).map_err(|err| FileProviderError::Io {
+                        path: path.clone(),
+                        err,
+                    })?;
+                    self.current_path = path;
+                    self.line = 0;
+                    self.reader.insert(BufReader::new(file))
+                }
+            };
+
+            let event = match self.format {
+                FileFormat::MsgPack => Self::read_msgpack(reader, &self.current_path)?,
+                FileFormat::JsonLines => {
+                    Self::read_json_line(reader, &self.current_path, &mut self.line)?
+                }
+            };
+            match event {
+                Some(event) => return Ok(Some(event)),
+                None => self.reader = None,
+            }
+        }
+    }
+
+    fn read_msgpack(reader: &mut BufReader<File>, path: &Path) -> Result<Option<MarketDataEvent>> {
+        let at_end = reader
+            .fill_buf()
+            .map_err(|err| FileProviderError::Io {
+                path: path.to_path_buf(),
+                err,
+            })?
+            .is_empty();
+        if at_end {
+            return Ok(None);
+        }
+        rmp_serde::from_read(reader)
+            .map(Some)
+            .map_err(|err| FileProviderError::MsgPack {
+                path: path.to_path_buf(),
+                err,
+            })
+    }
+
+    fn read_json_line(
+        reader: &mut BufReader<File>,
+        path: &Path,
+        line: &mut usize,
+    ) -> Result<Option<MarketDataEvent>> {
+        let mut buf = String::new();
+        loop {
+            buf.clear();
+            let read = reader
+                .read_line(&mut buf)
+                .map_err(|err| FileProviderError::Io {
+                    path: path.to_path_buf(),
+                    err,
+                })?;
+            if read == 0 {
+                return Ok(None);
+            }
+            *line += 1;
+            if buf.trim().is_empty() {
+                continue;
+            }
+            return serde_json::from_str(&buf)
+                .map(Some)
+                .map_err(|err| FileProviderError::Json {
+                    path: path.to_path_buf(),
+                    line: *line,
+                    err,
+                });
+        }
+    }
+
+    fn list_files<F: Fn(&str) -> bool>(dir: &Path, filter: F) -> Result<Vec<PathBuf>> {
+        let to_error = |err| FileProviderError::Io {
+            path: dir.to_path_buf(),
+            err,
+        };
+        let mut files = vec![];
+        for entry in std::fs::read_dir(dir).map_err(to_error)? {
+            let path = entry.map_err(to_error)?.path();
+            let matched = matches!(path.file_name(), Some(name) if filter(&name.to_string_lossy()));
+            if path.is_file() && matched {
+                files.push(path);
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+}
+
+impl SimulatedTradingMarketDataProvider for FileMarketDataProvider {
+    fn next_event(&mut self) -> Option<MarketDataEvent> {
+        if self.exhausted {
+            return None;
+        }
+        match self.read_event() {
+            Ok(Some(event)) => Some(event),
+            Ok(None) => {
+                self.exhausted = true;
+                None
+            }
+            Err(err) => {
+                // position in broken file is unknown, so the stream can't be continued
+                error!("failed to read market data: {:?}", err);
+                self.exhausted = true;
+                None
+            }
+        }
+    }
+}
+
+/// Matches name against pattern with `*` (any sequence) and `?` (any char) wildcards
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // position of the last `*` and name position it was matched at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_n)) => {
+                    backtrack = Some((star_p, star_n + 1));
+                    p = star_p + 1;
+                    n = star_n + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
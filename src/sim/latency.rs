@@ -0,0 +1,138 @@
+use crate::core::types::Latency;
+use rand::distributions::{Distribution, Uniform, WeightedIndex};
+use rand::RngCore;
+use rand_distr::{LogNormal, Normal};
+use std::fmt::Debug;
+
+#[derive(Debug)]
+pub enum LatencyModelError {
+    InvalidParameters(String),
+}
+
+type Result<T> = std::result::Result<T, LatencyModelError>;
+
+pub trait LatencyModel: Debug + Send + Sync {
+    fn sample(&self, rng: &mut dyn RngCore) -> Latency;
+}
+
+fn to_latency(value: f64) -> Latency {
+    value.max(0.0).round() as Latency
+}
+
+#[derive(Clone, Debug)]
+pub struct ConstantLatency {
+    latency: Latency,
+}
+
+impl ConstantLatency {
+    pub fn new(latency: Latency) -> Self {
+        Self { latency }
+    }
+}
+
+impl LatencyModel for ConstantLatency {
+    fn sample(&self, _rng: &mut dyn RngCore) -> Latency {
+        self.latency
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct UniformLatency {
+    distribution: Uniform<Latency>,
+}
+
+impl UniformLatency {
+    pub fn new(min: Latency, max: Latency) -> Result<Self> {
+        if min > max {
+            return Err(LatencyModelError::InvalidParameters(format!(
+                "min latency {} is greater than max latency {}",
+                min, max
+            )));
+        }
+        Ok(Self {
+            distribution: Uniform::new_inclusive(min, max),
+        })
+    }
+}
+
+impl LatencyModel for UniformLatency {
+    fn sample(&self, rng: &mut dyn RngCore) -> Latency {
+        self.distribution.sample(rng)
+    }
+}
+
+/// Normally distributed latency. Negative samples are truncated to zero
+#[derive(Clone, Debug)]
+pub struct NormalLatency {
+    distribution: Normal<f64>,
+}
+
+impl NormalLatency {
+    pub fn new(mean: f64, std_dev: f64) -> Result<Self> {
+        match Normal::new(mean, std_dev) {
+            Ok(distribution) => Ok(Self { distribution }),
+            Err(err) => Err(LatencyModelError::InvalidParameters(format!("{:?}", err))),
+        }
+    }
+}
+
+impl LatencyModel for NormalLatency {
+    fn sample(&self, rng: &mut dyn RngCore) -> Latency {
+        to_latency(self.distribution.sample(rng))
+    }
+}
+
+/// Fat-tailed latency: ln(latency) ~ N(mu, sigma)
+#[derive(Clone, Debug)]
+pub struct LogNormalLatency {
+    distribution: LogNormal<f64>,
+}
+
+impl LogNormalLatency {
+    pub fn new(mu: f64, sigma: f64) -> Result<Self> {
+        match LogNormal::new(mu, sigma) {
+            Ok(distribution) => Ok(Self { distribution }),
+            Err(err) => Err(LatencyModelError::InvalidParameters(format!("{:?}", err))),
+        }
+    }
+}
+
+impl LatencyModel for LogNormalLatency {
+    fn sample(&self, rng: &mut dyn RngCore) -> Latency {
+        to_latency(self.distribution.sample(rng))
+    }
+}
+
+/// Latency sampled from observed histogram. Bin is chosen proportionally to its count
+/// and latency is uniformly distributed inside the bin [bin_edges[i], bin_edges[i + 1])
+#[derive(Clone, Debug)]
+pub struct EmpiricalLatency {
+    bin_edges: Vec<Latency>,
+    bins: WeightedIndex<u64>,
+}
+
+impl EmpiricalLatency {
+    pub fn new(bin_edges: Vec<Latency>, counts: Vec<u64>) -> Result<Self> {
+        if bin_edges.len() != counts.len() + 1 {
+            return Err(LatencyModelError::InvalidParameters(
+                "number of bin edges should be number of counts + 1".to_string(),
+            ));
+        }
+        if bin_edges.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(LatencyModelError::InvalidParameters(
+                "bin edges should be strictly increasing".to_string(),
+            ));
+        }
+        match WeightedIndex::new(counts) {
+            Ok(bins) => Ok(Self { bin_edges, bins }),
+            Err(err) => Err(LatencyModelError::InvalidParameters(format!("{:?}", err))),
+        }
+    }
+}
+
+impl LatencyModel for EmpiricalLatency {
+    fn sample(&self, rng: &mut dyn RngCore) -> Latency {
+        let bin = self.bins.sample(rng);
+        Uniform::new(self.bin_edges[bin], self.bin_edges[bin + 1]).sample(rng)
+    }
+}
@@ -0,0 +1,61 @@
+use super::environment::SimulatedTradingMarketDataProvider;
+use crate::core::market_data::MarketDataEvent;
+use crate::core::types::Timestamp;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Merges several market data providers, each sorted by exchange ts, into one sorted stream.
+/// Events with equal exchange ts go in order the providers were added
+#[derive(Default)]
+pub struct MergedMarketDataProvider {
+    providers: Vec<Box<dyn SimulatedTradingMarketDataProvider + Send>>,
+    // the next event of each provider, taken by its index from heap
+    heads: Vec<Option<MarketDataEvent>>,
+    heap: BinaryHeap<Reverse<(Timestamp, usize)>>,
+    initialized: bool,
+}
+
+impl MergedMarketDataProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_provider<T: SimulatedTradingMarketDataProvider + Send + 'static>(
+        mut self,
+        provider: T,
+    ) -> Self {
+        self.add_provider(Box::new(provider));
+        self
+    }
+
+    pub fn add_provider(&mut self, provider: Box<dyn SimulatedTradingMarketDataProvider + Send>) {
+        self.providers.push(provider);
+        self.heads.push(None);
+        if self.initialized {
+            self.pull(self.providers.len() - 1);
+        }
+    }
+
+    fn pull(&mut self, idx: usize) {
+        if let Some(event) = self.providers[idx].next_event() {
+            self.heap.push(Reverse((event.exchange_timestamp(), idx)));
+            self.heads[idx] = Some(event);
+        }
+    }
+}
+
+impl SimulatedTradingMarketDataProvider for MergedMarketDataProvider {
+    fn next_event(&mut self) -> Option<MarketDataEvent> {
+        if !self.initialized {
+            self.initialized = true;
+            for idx in 0..self.providers.len() {
+                self.pull(idx);
+            }
+        }
+
+        let Reverse((_, idx)) = self.heap.pop()?;
+        let event = self.heads[idx].take();
+        self.pull(idx);
+        event
+    }
+}
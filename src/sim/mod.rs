@@ -1,2 +1,9 @@
 pub mod broker;
+pub mod csv_provider;
 pub mod environment;
+pub mod fees;
+pub mod file_provider;
+pub mod latency;
+pub mod merge_provider;
+pub mod report;
+pub mod slippage;
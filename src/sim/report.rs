@@ -0,0 +1,336 @@
+use crate::core::events::{Event, OrderUpdate};
+use crate::core::position_tracker::PositionTracker;
+use crate::core::types::{ClientOrderId, Exchange, ExecutionType, Side, Symbol, Timestamp};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum ReportError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Csv(csv::Error),
+}
+
+impl From<std::io::Error> for ReportError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for ReportError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+impl From<csv::Error> for ReportError {
+    fn from(err: csv::Error) -> Self {
+        Self::Csv(err)
+    }
+}
+
+type Result<T> = std::result::Result<T, ReportError>;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Fill {
+    pub timestamp: Timestamp,
+    pub exchange: Exchange,
+    pub symbol: Symbol,
+    pub client_order_id: Option<ClientOrderId>,
+    pub side: Side,
+    pub quantity: f64,
+    pub price: f64,
+    pub commission: f64,
+    /// PnL realized by this fill, zero for fills which only increase position
+    pub realized_pnl: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EquityPoint {
+    pub timestamp: Timestamp,
+    pub equity: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SymbolStats {
+    pub exchange: Exchange,
+    pub symbol: Symbol,
+    pub fills: usize,
+    pub traded_quantity: f64,
+    pub traded_notional: f64,
+    pub realized_pnl: f64,
+    pub unrealized_pnl: f64,
+    pub commission: f64,
+    pub net_pnl: f64,
+    /// Share of position reducing fills with positive realized PnL
+    pub win_rate: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReportSummary {
+    pub initial_capital: f64,
+    pub final_equity: f64,
+    pub total_return: f64,
+    pub sharpe_ratio: Option<f64>,
+    pub sortino_ratio: Option<f64>,
+    pub max_drawdown: f64,
+    /// Traded notional divided by initial capital
+    pub turnover: f64,
+    pub win_rate: Option<f64>,
+    pub fills: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BacktestReport {
+    pub summary: ReportSummary,
+    pub symbols: Vec<SymbolStats>,
+    pub equity_curve: Vec<EquityPoint>,
+    pub fills: Vec<Fill>,
+}
+
+impl BacktestReport {
+    pub fn write_json<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    /// Writes summary.csv, symbols.csv, equity_curve.csv and fills.csv into directory
+    pub fn write_csv<P: AsRef<Path>>(&self, dir: P) -> Result<()> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+        write_csv_rows(dir.join("summary.csv"), [&self.summary])?;
+        write_csv_rows(dir.join("symbols.csv"), &self.symbols)?;
+        write_csv_rows(dir.join("equity_curve.csv"), &self.equity_curve)?;
+        write_csv_rows(dir.join("fills.csv"), &self.fills)?;
+        Ok(())
+    }
+}
+
+fn write_csv_rows<P: AsRef<Path>, T: Serialize, I: IntoIterator<Item = T>>(
+    path: P,
+    rows: I,
+) -> Result<()> {
+    let mut writer = csv::Writer::from_path(path)?;
+    for row in rows {
+        writer.serialize(row)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Collects fills and marked equity from events seen by actor during sim run
+#[derive(Debug)]
+pub struct ReportCollector {
+    initial_capital: f64,
+    /// Minimal time between equity curve points. Zero samples equity on every new timestamp
+    sample_interval: Timestamp,
+    /// Number of sample intervals in a year, used to annualize Sharpe and Sortino ratios
+    periods_per_year: f64,
+    position_tracker: PositionTracker,
+    fills: Vec<Fill>,
+    equity_curve: Vec<EquityPoint>,
+}
+
+impl ReportCollector {
+    pub fn new(initial_capital: f64) -> Self {
+        Self {
+            initial_capital,
+            sample_interval: 0,
+            periods_per_year: 1.0,
+            position_tracker: PositionTracker::new(),
+            fills: vec![],
+            equity_curve: vec![],
+        }
+    }
+
+    pub fn with_sample_interval(mut self, sample_interval: Timestamp) -> Self {
+        self.sample_interval = sample_interval;
+        self
+    }
+
+    pub fn with_periods_per_year(mut self, periods_per_year: f64) -> Self {
+        self.periods_per_year = periods_per_year;
+        self
+    }
+
+    pub fn on_event(&mut self, event: &Event) {
+        match event {
+            Event::UDSOrderUpdate(update) if update.execution_type == ExecutionType::TRADE => {
+                self.on_fill(update)
+            }
+            _ => self.position_tracker.on_event(event),
+        }
+        self.sample_equity(event.timestamp());
+    }
+
+    pub fn position_tracker(&self) -> &PositionTracker {
+        &self.position_tracker
+    }
+
+    pub fn equity(&self) -> f64 {
+        let commission: f64 = self
+            .position_tracker
+            .positions()
+            .map(|p| p.commission)
+            .sum();
+        self.initial_capital
+            + self.position_tracker.realized_pnl()
+            + self.position_tracker.unrealized_pnl()
+            - commission
+    }
+
+    pub fn report(&self) -> BacktestReport {
+        let final_equity = self.equity();
+        let returns: Vec<f64> = self
+            .equity_curve
+            .windows(2)
+            .map(|w| w[1].equity / w[0].equity - 1.0)
+            .collect();
+
+        let mut symbols: Vec<SymbolStats> = self
+            .position_tracker
+            .positions()
+            .map(|p| {
+                let fills: Vec<&Fill> = self
+                    .fills
+                    .iter()
+                    .filter(|f| f.exchange == p.exchange && f.symbol == p.symbol)
+                    .collect();
+                SymbolStats {
+                    exchange: p.exchange.clone(),
+                    symbol: p.symbol.clone(),
+                    fills: fills.len(),
+                    traded_quantity: fills.iter().map(|f| f.quantity).sum(),
+                    traded_notional: fills.iter().map(|f| f.quantity * f.price).sum(),
+                    realized_pnl: p.realized_pnl,
+                    unrealized_pnl: p.unrealized_pnl,
+                    commission: p.commission,
+                    net_pnl: p.realized_pnl + p.unrealized_pnl - p.commission,
+                    win_rate: win_rate(fills.into_iter()),
+                }
+            })
+            .collect();
+        symbols.sort_by(|a, b| (&a.exchange, &a.symbol).cmp(&(&b.exchange, &b.symbol)));
+
+        let traded_notional: f64 = symbols.iter().map(|s| s.traded_notional).sum();
+        let summary = ReportSummary {
+            initial_capital: self.initial_capital,
+            final_equity,
+            total_return: final_equity / self.initial_capital - 1.0,
+            sharpe_ratio: sharpe_ratio(&returns, self.periods_per_year),
+            sortino_ratio: sortino_ratio(&returns, self.periods_per_year),
+            max_drawdown: max_drawdown(&self.equity_curve),
+            turnover: traded_notional / self.initial_capital,
+            win_rate: win_rate(self.fills.iter()),
+            fills: self.fills.len(),
+        };
+
+        BacktestReport {
+            summary,
+            symbols,
+            equity_curve: self.equity_curve.clone(),
+            fills: self.fills.clone(),
+        }
+    }
+
+    fn on_fill(&mut self, update: &OrderUpdate) {
+        let realized_pnl_before = self.realized_pnl(&update.exchange, &update.symbol);
+        self.position_tracker
+            .on_event(&Event::UDSOrderUpdate(update.clone()));
+        let (quantity, price) = match (update.last_filled_qty, update.last_filled_price) {
+            (Some(quantity), Some(price)) => (quantity, price),
+            _ => return,
+        };
+        self.fills.push(Fill {
+            timestamp: update.timestamp,
+            exchange: update.exchange.clone(),
+            symbol: update.symbol.clone(),
+            client_order_id: update.client_order_id.clone(),
+            side: update.side.clone(),
+            quantity,
+            price,
+            commission: update.commission.unwrap_or(0.0),
+            realized_pnl: self.realized_pnl(&update.exchange, &update.symbol) - realized_pnl_before,
+        });
+    }
+
+    fn realized_pnl(&self, exchange: &str, symbol: &str) -> f64 {
+        self.position_tracker
+            .position(exchange, symbol)
+            .map(|p| p.realized_pnl)
+            .unwrap_or(0.0)
+    }
+
+    fn sample_equity(&mut self, ts: Timestamp) {
+        let equity = self.equity();
+        match self.equity_curve.last_mut() {
+            Some(last) if ts < last.timestamp + self.sample_interval.max(1) => {
+                // point stays at the start of interval but keeps the latest equity
+                last.equity = equity;
+            }
+            _ => self.equity_curve.push(EquityPoint {
+                timestamp: ts,
+                equity,
+            }),
+        }
+    }
+}
+
+fn win_rate<'a, I: Iterator<Item = &'a Fill>>(fills: I) -> Option<f64> {
+    let (wins, closing_fills) = fills
+        .filter(|f| f.realized_pnl != 0.0)
+        .fold((0, 0), |(wins, total), f| {
+            (wins + (f.realized_pnl > 0.0) as usize, total + 1)
+        });
+    match closing_fills {
+        0 => None,
+        _ => Some(wins as f64 / closing_fills as f64),
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+fn sharpe_ratio(returns: &[f64], periods_per_year: f64) -> Option<f64> {
+    if returns.len() < 2 {
+        return None;
+    }
+    let mean_return = mean(returns);
+    let variance = returns
+        .iter()
+        .map(|r| (r - mean_return).powi(2))
+        .sum::<f64>()
+        / (returns.len() - 1) as f64;
+    match variance > 0.0 {
+        true => Some(mean_return / variance.sqrt() * periods_per_year.sqrt()),
+        false => None,
+    }
+}
+
+fn sortino_ratio(returns: &[f64], periods_per_year: f64) -> Option<f64> {
+    if returns.len() < 2 {
+        return None;
+    }
+    let downside: Vec<f64> = returns.iter().map(|r| r.min(0.0).powi(2)).collect();
+    let downside_deviation = mean(&downside).sqrt();
+    match downside_deviation > 0.0 {
+        true => Some(mean(returns) / downside_deviation * periods_per_year.sqrt()),
+        false => None,
+    }
+}
+
+/// Largest relative decline of equity from its running peak
+fn max_drawdown(equity_curve: &[EquityPoint]) -> f64 {
+    let mut peak = f64::MIN;
+    let mut max_drawdown: f64 = 0.0;
+    for point in equity_curve {
+        peak = peak.max(point.equity);
+        if peak > 0.0 {
+            max_drawdown = max_drawdown.max((peak - point.equity) / peak);
+        }
+    }
+    max_drawdown
+}
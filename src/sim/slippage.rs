@@ -0,0 +1,74 @@
+use crate::core::market_data::MarketDataEvent;
+use crate::core::types::Side;
+use std::fmt::Debug;
+
+pub trait SlippageModel: Debug + Send + Sync {
+    /// Returns adverse price move for marketable fill. SimBroker moves fill price against order side by this value
+    fn slippage(&self, side: &Side, price: f64, quantity: f64, md: &MarketDataEvent) -> f64;
+}
+
+#[derive(Clone, Debug)]
+pub struct FixedBpsSlippage {
+    bps: f64,
+}
+
+impl FixedBpsSlippage {
+    pub fn new(bps: f64) -> Self {
+        Self { bps }
+    }
+}
+
+impl SlippageModel for FixedBpsSlippage {
+    fn slippage(&self, _side: &Side, price: f64, _quantity: f64, _md: &MarketDataEvent) -> f64 {
+        price * self.bps / 10_000.0
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SpreadFractionSlippage {
+    fraction: f64,
+}
+
+impl SpreadFractionSlippage {
+    pub fn new(fraction: f64) -> Self {
+        Self { fraction }
+    }
+}
+
+impl SlippageModel for SpreadFractionSlippage {
+    fn slippage(&self, _side: &Side, _price: f64, _quantity: f64, md: &MarketDataEvent) -> f64 {
+        match md {
+            MarketDataEvent::NewQuote(q) => (q.ask - q.bid).max(0.0) * self.fraction,
+            // spread is unknown for trades and bars and for book deltas, which may not contain top levels
+            MarketDataEvent::NewMarketTrade(_)
+            | MarketDataEvent::NewOrderBookUpdate(_)
+            | MarketDataEvent::NewBar(_) => 0.0,
+        }
+    }
+}
+
+/// Market impact proportional to square root of fill quantity relative to reference volume:
+/// price * coefficient * sqrt(quantity / reference_volume)
+#[derive(Clone, Debug)]
+pub struct SquareRootImpactSlippage {
+    coefficient: f64,
+    reference_volume: f64,
+}
+
+impl SquareRootImpactSlippage {
+    pub fn new(coefficient: f64, reference_volume: f64) -> Self {
+        Self {
+            coefficient,
+            reference_volume,
+        }
+    }
+}
+
+impl SlippageModel for SquareRootImpactSlippage {
+    fn slippage(&self, _side: &Side, price: f64, quantity: f64, _md: &MarketDataEvent) -> f64 {
+        if self.reference_volume <= 0.0 {
+            return 0.0;
+        }
+        price * self.coefficient * (quantity / self.reference_volume).sqrt()
+    }
+}
@@ -0,0 +1,236 @@
+use crossbeam_channel::unbounded;
+use geger::core::bar_aggregator::{BarAggregator, BarEventProvider};
+use geger::core::event_loop::EventProvider;
+use geger::core::events::Event;
+use geger::core::market_data::{Bar, BarType, MarketDataEvent, Quote, Trade};
+use geger::core::types::Timestamp;
+use geger::sim::broker::{SimBroker, SimBrokerConfig};
+use geger::sim::environment::{SimulatedEnvironment, SimulatedTradingMarketDataProvider};
+use std::collections::VecDeque;
+
+const EXCHANGE: &str = "test_exchange";
+const SYMBOL: &str = "test_symbol";
+
+fn trade(price: f64, size: f64, ts: Timestamp) -> Trade {
+    Trade {
+        event_id: None,
+        symbol: SYMBOL.to_string(),
+        exchange: EXCHANGE.to_string(),
+        last_price: price,
+        last_size: size,
+        exchange_timestamp: ts,
+        received_timestamp: ts,
+    }
+}
+
+fn quote(ts: Timestamp) -> Quote {
+    Quote {
+        event_id: None,
+        symbol: SYMBOL.to_string(),
+        exchange: EXCHANGE.to_string(),
+        bid: 99.0,
+        ask: 100.0,
+        bid_size: None,
+        ask_size: None,
+        exchange_timestamp: ts,
+        received_timestamp: ts,
+    }
+}
+
+struct VecEventProvider {
+    events: VecDeque<Event>,
+}
+
+impl EventProvider for VecEventProvider {
+    fn next_event(&mut self) -> Option<Event> {
+        self.events.pop_front()
+    }
+}
+
+struct VecMDProvider {
+    events: VecDeque<MarketDataEvent>,
+}
+
+impl SimulatedTradingMarketDataProvider for VecMDProvider {
+    fn next_event(&mut self) -> Option<MarketDataEvent> {
+        self.events.pop_front()
+    }
+}
+
+fn ohlcv(bar: &Bar) -> (f64, f64, f64, f64, f64) {
+    (bar.open, bar.high, bar.low, bar.close, bar.volume)
+}
+
+#[test]
+fn time_bars_aligned_to_interval() {
+    let mut aggregator = BarAggregator::new(BarType::Time(100));
+    assert_eq!(aggregator.on_trade(&trade(10.0, 1.0, 120)), None);
+    assert_eq!(aggregator.on_trade(&trade(12.0, 2.0, 150)), None);
+    assert_eq!(aggregator.on_trade(&trade(9.0, 1.0, 199)), None);
+    assert!(aggregator.on_timestamp(199).is_empty());
+
+    let bars = aggregator.on_timestamp(200);
+    assert_eq!(bars.len(), 1);
+    let bar = &bars[0];
+    assert_eq!(ohlcv(bar), (10.0, 12.0, 9.0, 9.0, 4.0));
+    assert_eq!(bar.vwap, Some(43.0 / 4.0));
+    assert_eq!(bar.trade_count, 3);
+    assert_eq!(bar.start_timestamp, 100);
+    assert_eq!(bar.exchange_timestamp, 200);
+    assert_eq!(bar.bar_type, Some(BarType::Time(100)));
+
+    // interval without trades produces no bar, next trade closes bar of its previous interval
+    assert_eq!(aggregator.on_trade(&trade(11.0, 1.0, 420)), None);
+    let bar = aggregator.on_trade(&trade(13.0, 1.0, 510)).unwrap();
+    assert_eq!(ohlcv(&bar), (11.0, 11.0, 11.0, 11.0, 1.0));
+    assert_eq!(bar.start_timestamp, 400);
+    assert_eq!(bar.exchange_timestamp, 500);
+    assert_eq!(bar.received_timestamp, 510);
+}
+
+#[test]
+fn tick_volume_and_dollar_bars_closed_by_threshold_trade() {
+    let mut tick = BarAggregator::new(BarType::Tick(2));
+    let mut volume = BarAggregator::new(BarType::Volume(3.0));
+    let mut dollar = BarAggregator::new(BarType::Dollar(250.0));
+    let trades = [
+        trade(100.0, 1.0, 10),
+        trade(101.0, 1.5, 20),
+        trade(99.0, 1.0, 30),
+        trade(98.0, 2.0, 40),
+    ];
+
+    let mut tick_bars = vec![];
+    let mut volume_bars = vec![];
+    let mut dollar_bars = vec![];
+    for trade in trades.iter() {
+        tick_bars.extend(tick.on_trade(trade));
+        volume_bars.extend(volume.on_trade(trade));
+        dollar_bars.extend(dollar.on_trade(trade));
+    }
+
+    assert_eq!(tick_bars.len(), 2);
+    assert_eq!(ohlcv(&tick_bars[0]), (100.0, 101.0, 100.0, 101.0, 2.5));
+    assert_eq!(tick_bars[0].start_timestamp, 10);
+    assert_eq!(tick_bars[0].exchange_timestamp, 20);
+    assert_eq!(ohlcv(&tick_bars[1]), (99.0, 99.0, 98.0, 98.0, 3.0));
+
+    // 3.5 lots are traded when threshold is reached
+    assert_eq!(volume_bars.len(), 1);
+    assert_eq!(ohlcv(&volume_bars[0]), (100.0, 101.0, 99.0, 99.0, 3.5));
+    assert_eq!(volume_bars[0].exchange_timestamp, 30);
+
+    // 100 + 151.5
+    assert_eq!(dollar_bars.len(), 2);
+    assert_eq!(dollar_bars[0].trade_count, 2);
+    assert_eq!(dollar_bars[1].trade_count, 2);
+
+    // unfinished bar is closed at its last trade
+    assert!(tick.flush().is_empty());
+    let flushed = volume.flush();
+    assert_eq!(flushed.len(), 1);
+    assert_eq!(ohlcv(&flushed[0]), (98.0, 98.0, 98.0, 98.0, 2.0));
+    assert_eq!(flushed[0].exchange_timestamp, 40);
+    assert!(volume.flush().is_empty());
+}
+
+#[test]
+fn bar_event_provider_emits_bars_around_events() {
+    let events = vec![
+        Event::NewMarketTrade(trade(10.0, 1.0, 110)),
+        Event::NewMarketTrade(trade(11.0, 1.0, 150)),
+        Event::NewQuote(quote(230)),
+        Event::NewMarketTrade(trade(12.0, 1.0, 240)),
+    ];
+    let mut provider = BarEventProvider::new(VecEventProvider {
+        events: events.into(),
+    })
+    .with_bar_aggregator(BarAggregator::new(BarType::Time(100)))
+    .with_bar_aggregator(BarAggregator::new(BarType::Tick(2)));
+
+    let mut kinds = vec![];
+    while let Some(event) = provider.next_event() {
+        kinds.push(match event {
+            Event::NewMarketTrade(t) => format!("trade {}", t.exchange_timestamp),
+            Event::NewQuote(q) => format!("quote {}", q.exchange_timestamp),
+            Event::NewBar(b) => format!("bar {:?} {}", b.bar_type.unwrap(), b.exchange_timestamp),
+            _ => unreachable!(),
+        });
+    }
+    assert_eq!(
+        kinds,
+        vec![
+            "trade 110",
+            "trade 150",
+            "bar Tick(2) 150",
+            "bar Time(100) 200",
+            "quote 230",
+            "trade 240",
+            // unfinished bars are flushed at the end
+            "bar Tick(2) 240",
+            "bar Time(100) 300",
+        ]
+    );
+}
+
+#[test]
+fn bar_event_provider_closes_time_bars_by_exchange_time() {
+    let mut late_quote = quote(190);
+    late_quote.received_timestamp = 210;
+    let events = vec![
+        Event::NewMarketTrade(trade(10.0, 1.0, 110)),
+        Event::NewQuote(late_quote),
+        Event::NewMarketTrade(trade(12.0, 1.0, 240)),
+    ];
+    let mut provider = BarEventProvider::new(VecEventProvider {
+        events: events.into(),
+    })
+    .with_bar_aggregator(BarAggregator::new(BarType::Time(100)));
+
+    let mut timestamps = vec![];
+    while let Some(event) = provider.next_event() {
+        timestamps.push(event.exchange_timestamp());
+    }
+    assert_eq!(timestamps, vec![110, 190, 200, 240, 300]);
+}
+
+#[test]
+fn sim_environment_delivers_bars_with_market_data_latency() {
+    let md = vec![
+        MarketDataEvent::NewMarketTrade(trade(10.0, 1.0, 110)),
+        MarketDataEvent::NewMarketTrade(trade(11.0, 1.0, 190)),
+        MarketDataEvent::NewMarketTrade(trade(12.0, 1.0, 230)),
+    ];
+    let mut env = SimulatedEnvironment::new(VecMDProvider { events: md.into() }, None);
+    let (_sender, receiver) = unbounded();
+    let config = SimBrokerConfig::new(false, Some(10), None);
+    env.add_broker(SimBroker::new(EXCHANGE.to_string(), receiver, config))
+        .unwrap();
+    env.add_bar_aggregator(BarAggregator::new(BarType::Time(100)));
+
+    let mut events = vec![];
+    while let Some(event) = env.next_event() {
+        events.push(event);
+    }
+    let timestamps: Vec<(Timestamp, Timestamp)> = events
+        .iter()
+        .map(|e| (e.exchange_timestamp(), e.timestamp()))
+        .collect();
+    assert_eq!(
+        timestamps,
+        vec![(110, 120), (190, 200), (200, 210), (230, 240), (300, 310)]
+    );
+    match &events[2] {
+        Event::NewBar(bar) => {
+            assert_eq!(ohlcv(bar), (10.0, 11.0, 10.0, 11.0, 2.0));
+        }
+        other => panic!("expected bar, got {:?}", other),
+    }
+    // the last bar is flushed when md is over
+    match &events[4] {
+        Event::NewBar(bar) => {
+            assert_eq!(ohlcv(bar), (12.0, 12.0, 12.0, 12.0, 1.0));
+        }
+        other => panic!("expected bar, got {:?}", other),
+    }
+}
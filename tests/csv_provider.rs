@@ -0,0 +1,137 @@
+use geger::core::market_data::MarketDataEvent;
+use geger::sim::csv_provider::{
+    CsvField, CsvMarketDataProvider, CsvProviderConfig, CsvProviderError, TimestampUnit,
+};
+use geger::sim::environment::SimulatedTradingMarketDataProvider;
+use std::fs;
+use std::path::PathBuf;
+
+fn write_csv(name: &str, content: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("geger_{}_{}.csv", name, std::process::id()));
+    fs::write(&path, content).unwrap();
+    path
+}
+
+fn read_all(provider: &mut CsvMarketDataProvider) -> Vec<MarketDataEvent> {
+    let mut events = vec![];
+    while let Some(event) = provider.next_event() {
+        events.push(event);
+    }
+    events
+}
+
+#[test]
+fn csv_trades_with_column_mapping_and_defaults() {
+    let path = write_csv(
+        "trades",
+        "ts;px;qty;sym\n\
+         1650000000.5;100.5;2;BTCUSDT\n\
+         1650000001;101;0.5;\n",
+    );
+    let config = CsvProviderConfig::trades()
+        .with_delimiter(b';')
+        .with_column(CsvField::ExchangeTimestamp, "ts")
+        .with_column(CsvField::Price, "px")
+        .with_column(CsvField::Size, "qty")
+        .with_column(CsvField::Symbol, "sym")
+        .with_timestamp_unit(TimestampUnit::Seconds)
+        .with_default_exchange("binance".to_string())
+        .with_default_symbol("ETHUSDT".to_string());
+    let mut provider = CsvMarketDataProvider::new(&path, config).unwrap();
+    let events = read_all(&mut provider);
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(events.len(), 2);
+    match (&events[0], &events[1]) {
+        (MarketDataEvent::NewMarketTrade(first), MarketDataEvent::NewMarketTrade(second)) => {
+            assert_eq!(first.exchange, "binance");
+            assert_eq!(first.symbol, "BTCUSDT");
+            assert_eq!(first.last_price, 100.5);
+            assert_eq!(first.last_size, 2.0);
+            assert_eq!(first.exchange_timestamp, 1_650_000_000_500);
+            assert_eq!(first.received_timestamp, 1_650_000_000_500);
+            // empty symbol is filled with default
+            assert_eq!(second.symbol, "ETHUSDT");
+            assert_eq!(second.exchange_timestamp, 1_650_000_001_000);
+        }
+        other => panic!("expected trades, got {:?}", other),
+    }
+}
+
+#[test]
+fn csv_quotes_and_timestamp_units() {
+    let path = write_csv(
+        "quotes",
+        "exchange,symbol,bid,ask,bid_size,exchange_timestamp,received_timestamp\n\
+         ftx,BTC-PERP,99.5,100,3,1650000000123456789,1650000000124000000\n",
+    );
+    let config = CsvProviderConfig::quotes().with_timestamp_unit(TimestampUnit::Nanoseconds);
+    let mut provider = CsvMarketDataProvider::new(&path, config).unwrap();
+    let events = read_all(&mut provider);
+    fs::remove_file(&path).unwrap();
+
+    match &events[..] {
+        [MarketDataEvent::NewQuote(quote)] => {
+            assert_eq!(quote.exchange, "ftx");
+            assert_eq!(quote.bid, 99.5);
+            assert_eq!(quote.ask, 100.0);
+            assert_eq!(quote.bid_size, Some(3.0));
+            assert_eq!(quote.ask_size, None);
+            assert_eq!(quote.exchange_timestamp, 1_650_000_000_123);
+            assert_eq!(quote.received_timestamp, 1_650_000_000_124);
+        }
+        other => panic!("expected quote, got {:?}", other),
+    }
+
+    assert_eq!(TimestampUnit::Microseconds.to_millis("1500"), Some(1));
+    assert_eq!(TimestampUnit::Milliseconds.to_millis("12.7"), Some(12));
+    assert_eq!(TimestampUnit::Seconds.to_millis("-1"), None);
+    // out of timestamp range
+    assert_eq!(
+        TimestampUnit::Seconds.to_millis("18446744073709551"),
+        Some(18_446_744_073_709_551_000)
+    );
+    assert_eq!(TimestampUnit::Seconds.to_millis("18446744073709552"), None);
+    assert_eq!(TimestampUnit::Seconds.to_millis("1e300"), None);
+}
+
+#[test]
+fn csv_errors_are_reported() {
+    let path = write_csv(
+        "no_exchange",
+        "last_price,last_size,exchange_timestamp\n1,1,1\n",
+    );
+    let err = CsvMarketDataProvider::new(&path, CsvProviderConfig::trades()).unwrap_err();
+    assert!(matches!(err, CsvProviderError::MissingColumn(column) if column == "exchange"));
+    fs::remove_file(&path).unwrap();
+
+    let path = write_csv(
+        "invalid",
+        "exchange,symbol,last_price,last_size,exchange_timestamp\n\
+         ftx,BTC,abc,1,1\n\
+         ftx,BTC,10,1,2\n",
+    );
+    let mut provider = CsvMarketDataProvider::new(&path, CsvProviderConfig::trades()).unwrap();
+    match provider.read_event() {
+        Err(CsvProviderError::InvalidValue {
+            line,
+            column,
+            value,
+        }) => {
+            assert_eq!(line, 2);
+            assert_eq!(column, "last_price");
+            assert_eq!(value, "abc");
+        }
+        other => panic!("expected invalid value, got {:?}", other),
+    }
+
+    // invalid row ends the stream unless skipping is enabled
+    let mut provider = CsvMarketDataProvider::new(&path, CsvProviderConfig::trades()).unwrap();
+    assert!(read_all(&mut provider).is_empty());
+    let config = CsvProviderConfig::trades().with_skip_invalid_rows(true);
+    let mut provider = CsvMarketDataProvider::new(&path, config).unwrap();
+    let events = read_all(&mut provider);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].exchange_timestamp(), 2);
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,233 @@
+use geger::core::actions_context::ActionsContext;
+use geger::core::engine::{Engine, RunError};
+use geger::core::event_loop::{Actor, EventProvider, StopHandle};
+use geger::core::events::Event;
+use geger::core::gateway_router::NewOrderRequest;
+use geger::core::market_data::{MarketDataEvent, Quote};
+use geger::core::message_bus::{
+    CrossbeamMessageSender, LoggerMessageHandler, Message, MessageHandler, SimpleMessage, Topic,
+};
+use geger::core::types::{OrderStatus, OrderType, Side, TimeInForce, Timestamp};
+use geger::sim::environment::SimulatedTradingMarketDataProvider;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+const EXCHANGE: &str = "test_exchange";
+const SYMBOL: &str = "test_symbol";
+
+struct VecMDProvider {
+    events: VecDeque<MarketDataEvent>,
+}
+
+impl VecMDProvider {
+    fn new(quotes: &[(f64, f64, Timestamp)]) -> Self {
+        let events = quotes
+            .iter()
+            .map(|&(bid, ask, ts)| {
+                MarketDataEvent::NewQuote(Quote {
+                    event_id: None,
+                    symbol: SYMBOL.to_string(),
+                    exchange: EXCHANGE.to_string(),
+                    bid,
+                    ask,
+                    bid_size: None,
+                    ask_size: None,
+                    exchange_timestamp: ts,
+                    received_timestamp: ts,
+                })
+            })
+            .collect();
+        Self { events }
+    }
+}
+
+impl SimulatedTradingMarketDataProvider for VecMDProvider {
+    fn next_event(&mut self) -> Option<MarketDataEvent> {
+        self.events.pop_front()
+    }
+}
+
+#[derive(Debug)]
+struct OrderOnFirstQuote {
+    order_sent: bool,
+    panic_on_fill: bool,
+}
+
+impl Actor<SimpleMessage, CrossbeamMessageSender<SimpleMessage>> for OrderOnFirstQuote {
+    fn on_event(
+        &mut self,
+        event: &Event,
+        actions_context: &mut ActionsContext<SimpleMessage, CrossbeamMessageSender<SimpleMessage>>,
+    ) {
+        match event {
+            Event::NewQuote(quote) if !self.order_sent => {
+                self.order_sent = true;
+                for (client_order_id, price) in [("1", quote.ask), ("2", quote.bid - 10.0)] {
+                    let request = NewOrderRequest {
+                        request_id: client_order_id.to_string(),
+                        client_order_id: client_order_id.to_string(),
+                        exchange: EXCHANGE.to_string(),
+                        r#type: OrderType::LIMIT,
+                        time_in_force: TimeInForce::GTC,
+                        price: Some(price),
+                        trigger_price: None,
+                        symbol: SYMBOL.to_string(),
+                        quantity: 1.0,
+                        side: Side::BUY,
+                        creation_ts: quote.received_timestamp,
+                    };
+                    actions_context.send_order(request).unwrap();
+                }
+            }
+            Event::UDSOrderUpdate(update)
+                if self.panic_on_fill && update.order_status == OrderStatus::FILLED =>
+            {
+                panic!("unexpected fill")
+            }
+            _ => {}
+        }
+    }
+}
+
+fn new_engine(
+    panic_on_fill: bool,
+) -> Engine<
+    OrderOnFirstQuote,
+    SimpleMessage,
+    CrossbeamMessageSender<SimpleMessage>,
+    LoggerMessageHandler,
+> {
+    let mut engine = Engine::new();
+    engine.add_exchange(EXCHANGE.to_string());
+    engine.add_actor(Arc::new(Mutex::new(OrderOnFirstQuote {
+        order_sent: false,
+        panic_on_fill,
+    })));
+    engine.add_message_handler(Arc::new(Mutex::new(LoggerMessageHandler::default())));
+    engine
+}
+
+#[test]
+fn run_returns_event_counts_and_broker_state() {
+    let md_provider =
+        VecMDProvider::new(&[(99.0, 100.0, 100), (99.0, 100.0, 110), (99.0, 100.0, 120)]);
+    let result = new_engine(false)
+        .run_with_sim_environment(md_provider, None, HashMap::new(), true, StopHandle::new())
+        .unwrap();
+
+    assert!(result.errors.is_empty());
+    assert_eq!(result.event_counts.quotes, 3);
+    assert_eq!(result.event_counts.responses, 2);
+    // two NEW updates and one fill
+    assert_eq!(result.event_counts.order_updates, 3);
+    assert_eq!(result.event_counts.total, 8);
+
+    let broker = &result.brokers[EXCHANGE];
+    assert_eq!(broker.open_orders.len(), 1);
+    assert_eq!(broker.open_orders[0].client_order_id(), "2");
+    assert_eq!(broker.done_orders.len(), 1);
+    assert_eq!(broker.done_orders[0].status(), &OrderStatus::FILLED);
+}
+
+#[test]
+fn run_reports_actor_panic() {
+    let md_provider =
+        VecMDProvider::new(&[(99.0, 100.0, 100), (99.0, 100.0, 110), (99.0, 100.0, 120)]);
+    let result = new_engine(true)
+        .run_with_sim_environment(md_provider, None, HashMap::new(), true, StopHandle::new())
+        .unwrap();
+
+    assert_eq!(result.errors.len(), 1);
+    match &result.errors[0] {
+        RunError::ActorPanic(actor_panic) => {
+            assert_eq!(actor_panic.message, "unexpected fill");
+            assert!(matches!(actor_panic.event, Event::UDSOrderUpdate(_)));
+        }
+        err => panic!("unexpected error: {:?}", err),
+    }
+    assert!(result.event_counts.quotes < 3);
+    assert_eq!(result.brokers[EXCHANGE].done_orders.len(), 1);
+}
+
+#[test]
+fn run_stops_on_requested_stop() {
+    let md_provider =
+        VecMDProvider::new(&[(99.0, 100.0, 100), (99.0, 100.0, 110), (99.0, 100.0, 120)]);
+    let stop_handle = StopHandle::new();
+    stop_handle.stop();
+    let result = new_engine(false)
+        .run_with_sim_environment(md_provider, None, HashMap::new(), true, stop_handle)
+        .unwrap();
+
+    assert!(result.errors.is_empty());
+    assert_eq!(result.event_counts.total, 0);
+    assert!(result.brokers[EXCHANGE].open_orders.is_empty());
+}
+
+struct EndlessQuotes {
+    ts: Timestamp,
+}
+
+impl EventProvider for EndlessQuotes {
+    fn next_event(&mut self) -> Option<Event> {
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        self.ts += 1;
+        Some(Event::NewQuote(Quote {
+            event_id: None,
+            symbol: SYMBOL.to_string(),
+            exchange: EXCHANGE.to_string(),
+            bid: 99.0,
+            ask: 100.0,
+            bid_size: None,
+            ask_size: None,
+            exchange_timestamp: self.ts,
+            received_timestamp: self.ts,
+        }))
+    }
+}
+
+#[derive(Debug, Default)]
+struct StopMessageHandler {
+    event_loop_stopped: bool,
+}
+
+impl MessageHandler<SimpleMessage, CrossbeamMessageSender<SimpleMessage>> for StopMessageHandler {
+    fn on_new_message(
+        &mut self,
+        message: &SimpleMessage,
+        _actions_context: &ActionsContext<SimpleMessage, CrossbeamMessageSender<SimpleMessage>>,
+    ) {
+        self.event_loop_stopped |= message.is_event_loop_stopped_message();
+    }
+
+    fn get_topics(&self) -> Vec<Topic> {
+        vec![]
+    }
+}
+
+#[test]
+fn stop_handle_stops_event_loop_and_message_bus() {
+    let handler = Arc::new(Mutex::new(StopMessageHandler::default()));
+    let mut engine: Engine<
+        OrderOnFirstQuote,
+        SimpleMessage,
+        CrossbeamMessageSender<SimpleMessage>,
+        StopMessageHandler,
+    > = Engine::new();
+    engine.add_exchange(EXCHANGE.to_string());
+    engine.add_actor(Arc::new(Mutex::new(OrderOnFirstQuote {
+        order_sent: true,
+        panic_on_fill: false,
+    })));
+    engine.add_message_handler(handler.clone());
+    let execution_info = engine
+        .start_with_event_provider(EndlessQuotes { ts: 0 }, true)
+        .unwrap();
+
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    execution_info.stop_handle.stop();
+    for th in execution_info.threads {
+        th.unwrap().join().unwrap()
+    }
+    assert!(handler.lock().unwrap().event_loop_stopped);
+}
@@ -0,0 +1,126 @@
+use geger::core::market_data::{MarketDataEvent, Quote, Trade};
+use geger::core::types::Timestamp;
+use geger::sim::environment::SimulatedTradingMarketDataProvider;
+use geger::sim::file_provider::{FileFormat, FileMarketDataProvider, FileProviderError};
+use std::fs;
+use std::path::PathBuf;
+
+fn trade(ts: Timestamp) -> MarketDataEvent {
+    MarketDataEvent::NewMarketTrade(Trade {
+        event_id: Some(ts.to_string()),
+        symbol: "test_symbol".to_string(),
+        exchange: "test_exchange".to_string(),
+        last_price: 100.0,
+        last_size: 1.5,
+        exchange_timestamp: ts,
+        received_timestamp: ts,
+    })
+}
+
+fn quote(ts: Timestamp) -> MarketDataEvent {
+    MarketDataEvent::NewQuote(Quote {
+        event_id: None,
+        symbol: "test_symbol".to_string(),
+        exchange: "test_exchange".to_string(),
+        bid: 99.0,
+        ask: 100.0,
+        bid_size: Some(2.0),
+        ask_size: None,
+        exchange_timestamp: ts,
+        received_timestamp: ts,
+    })
+}
+
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("geger_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn read_timestamps(provider: &mut FileMarketDataProvider) -> Vec<Timestamp> {
+    let mut timestamps = vec![];
+    while let Some(event) = provider.next_event() {
+        timestamps.push(event.exchange_timestamp());
+    }
+    timestamps
+}
+
+fn write_msgpack(path: PathBuf, events: &[MarketDataEvent]) {
+    let mut data = vec![];
+    for event in events {
+        data.extend(rmp_serde::to_vec(event).unwrap());
+    }
+    fs::write(path, data).unwrap();
+}
+
+#[test]
+fn msgpack_files_matched_by_glob_are_read_in_name_order() {
+    let dir = test_dir("msgpack");
+    write_msgpack(dir.join("md_2.msgpack"), &[trade(30), quote(40)]);
+    write_msgpack(dir.join("md_1.msgpack"), &[quote(10), trade(20)]);
+    write_msgpack(dir.join("other.msgpack"), &[trade(0)]);
+    fs::write(dir.join("md_3.json"), "").unwrap();
+
+    let pattern = dir.join("md_*.msgpack");
+    let mut provider =
+        FileMarketDataProvider::from_glob(pattern.to_str().unwrap(), FileFormat::MsgPack).unwrap();
+    let first = provider.next_event().unwrap();
+    assert_eq!(format!("{:?}", first), format!("{:?}", quote(10)));
+    assert_eq!(read_timestamps(&mut provider), vec![20, 30, 40]);
+
+    let mut provider = FileMarketDataProvider::from_dir(&dir, FileFormat::MsgPack).unwrap();
+    // empty json file yields no events
+    assert_eq!(read_timestamps(&mut provider), vec![10, 20, 30, 40, 0]);
+
+    let pattern = dir.join("*.csv");
+    let err = FileMarketDataProvider::from_glob(pattern.to_str().unwrap(), FileFormat::MsgPack)
+        .unwrap_err();
+    assert!(matches!(err, FileProviderError::NoFiles(_)));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn json_lines_errors_report_file_and_line() {
+    let dir = test_dir("json_lines");
+    let lines = [trade(10), quote(20)]
+        .iter()
+        .map(|event| serde_json::to_string(event).unwrap())
+        .collect::<Vec<String>>();
+    fs::write(
+        dir.join("a.jsonl"),
+        format!("{}\n\n{}\n", lines[0], lines[1]),
+    )
+    .unwrap();
+    fs::write(dir.join("b.jsonl"), format!("{}\nnot json\n", lines[0])).unwrap();
+
+    let mut provider = FileMarketDataProvider::from_dir(&dir, FileFormat::JsonLines).unwrap();
+    let mut timestamps = vec![];
+    let err = loop {
+        match provider.read_event() {
+            Ok(Some(event)) => timestamps.push(event.exchange_timestamp()),
+            Ok(None) => panic!("expected error"),
+            Err(err) => break err,
+        }
+    };
+    assert_eq!(timestamps, vec![10, 20, 10]);
+    match err {
+        FileProviderError::Json { path, line, .. } => {
+            assert_eq!(path, dir.join("b.jsonl"));
+            assert_eq!(line, 2);
+        }
+        other => panic!("expected json error, got {:?}", other),
+    }
+
+    // broken file ends the stream
+    let mut provider = FileMarketDataProvider::from_dir(&dir, FileFormat::JsonLines).unwrap();
+    assert_eq!(read_timestamps(&mut provider), vec![10, 20, 10]);
+
+    let mut provider =
+        FileMarketDataProvider::new(vec![dir.join("missing.jsonl")], FileFormat::JsonLines);
+    assert!(matches!(
+        provider.read_event(),
+        Err(FileProviderError::Io { .. })
+    ));
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,52 @@
+use geger::core::market_data::{MarketDataEvent, Trade};
+use geger::core::types::Timestamp;
+use geger::sim::environment::SimulatedTradingMarketDataProvider;
+use geger::sim::merge_provider::MergedMarketDataProvider;
+use std::collections::VecDeque;
+
+struct VecMDProvider {
+    events: VecDeque<MarketDataEvent>,
+}
+
+impl SimulatedTradingMarketDataProvider for VecMDProvider {
+    fn next_event(&mut self) -> Option<MarketDataEvent> {
+        self.events.pop_front()
+    }
+}
+
+fn provider(symbol: &str, timestamps: &[Timestamp]) -> VecMDProvider {
+    let events = timestamps
+        .iter()
+        .map(|&ts| {
+            MarketDataEvent::NewMarketTrade(Trade {
+                event_id: None,
+                symbol: symbol.to_string(),
+                exchange: "test_exchange".to_string(),
+                last_price: 100.0,
+                last_size: 1.0,
+                exchange_timestamp: ts,
+                received_timestamp: ts,
+            })
+        })
+        .collect();
+    VecMDProvider { events }
+}
+
+#[test]
+fn merged_provider_sorts_by_exchange_ts_with_stable_ties() {
+    let mut merged = MergedMarketDataProvider::new()
+        .with_provider(provider("a", &[10, 20, 20, 50]))
+        .with_provider(provider("b", &[]))
+        .with_provider(provider("c", &[5, 20, 60]))
+        .with_provider(provider("d", &[20, 30]));
+
+    let mut events = vec![];
+    while let Some(event) = merged.next_event() {
+        events.push(format!("{} {}", event.symbol(), event.exchange_timestamp()));
+    }
+    assert_eq!(
+        events,
+        vec!["c 5", "a 10", "a 20", "a 20", "c 20", "d 20", "d 30", "a 50", "c 60"]
+    );
+    assert!(merged.next_event().is_none());
+}
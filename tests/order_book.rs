@@ -0,0 +1,144 @@
+use geger::core::events::Event;
+use geger::core::market_data::{OrderBookUpdate, OrderBookUpdateType, PriceLevel};
+use geger::core::order_book::{OrderBook, OrderBookError};
+use geger::core::types::{Side, Timestamp};
+
+const EXCHANGE: &str = "test_exchange";
+const SYMBOL: &str = "test_symbol";
+
+fn levels(levels: &[(f64, f64)]) -> Vec<PriceLevel> {
+    levels
+        .iter()
+        .map(|&(price, size)| PriceLevel { price, size })
+        .collect()
+}
+
+fn book_update(
+    update_type: OrderBookUpdateType,
+    sequence: u64,
+    bids: &[(f64, f64)],
+    asks: &[(f64, f64)],
+    ts: Timestamp,
+) -> OrderBookUpdate {
+    OrderBookUpdate {
+        event_id: None,
+        symbol: SYMBOL.to_string(),
+        exchange: EXCHANGE.to_string(),
+        update_type,
+        sequence,
+        bids: levels(bids),
+        asks: levels(asks),
+        exchange_timestamp: ts,
+        received_timestamp: ts,
+    }
+}
+
+#[test]
+fn order_book_applies_snapshot_and_deltas() {
+    let mut book = OrderBook::new(EXCHANGE.to_string(), SYMBOL.to_string());
+    let snapshot = book_update(
+        OrderBookUpdateType::SNAPSHOT,
+        10,
+        &[(99.0, 1.0), (98.0, 2.0), (97.0, 5.0)],
+        &[(100.0, 1.0), (101.0, 3.0), (102.0, 4.0)],
+        100,
+    );
+    book.on_event(&Event::NewOrderBookUpdate(snapshot)).unwrap();
+    assert!(book.is_synced());
+    assert_eq!(
+        book.best_bid(),
+        Some(PriceLevel {
+            price: 99.0,
+            size: 1.0
+        })
+    );
+    assert_eq!(
+        book.best_ask(),
+        Some(PriceLevel {
+            price: 100.0,
+            size: 1.0
+        })
+    );
+    assert_eq!(book.mid_price(), Some(99.5));
+    assert_eq!(book.spread(), Some(1.0));
+    assert_eq!(
+        book.best_levels(&Side::BUY, 2),
+        levels(&[(99.0, 1.0), (98.0, 2.0)])
+    );
+    assert_eq!(book.depth_at_price(&Side::SELL, 101.0), 3.0);
+    assert_eq!(book.depth_at_price(&Side::SELL, 101.5), 0.0);
+
+    // 1 @ 100 + 3 @ 101 + 1 @ 102
+    assert_eq!(book.vwap_to_size(&Side::SELL, 5.0), Some(505.0 / 5.0));
+    assert_eq!(book.vwap_to_size(&Side::SELL, 9.0), None);
+
+    // best bid is removed, ask level is changed and new ask level is added
+    let delta = book_update(
+        OrderBookUpdateType::DELTA,
+        11,
+        &[(99.0, 0.0)],
+        &[(100.0, 2.5), (100.5, 1.0)],
+        110,
+    );
+    book.apply(&delta).unwrap();
+    assert_eq!(book.sequence(), Some(11));
+    assert_eq!(book.exchange_timestamp(), 110);
+    assert_eq!(
+        book.best_bid(),
+        Some(PriceLevel {
+            price: 98.0,
+            size: 2.0
+        })
+    );
+    assert_eq!(
+        book.best_levels(&Side::SELL, 10),
+        levels(&[(100.0, 2.5), (100.5, 1.0), (101.0, 3.0), (102.0, 4.0)])
+    );
+
+    // stale delta is ignored
+    let stale = book_update(OrderBookUpdateType::DELTA, 11, &[(98.0, 0.0)], &[], 111);
+    book.apply(&stale).unwrap();
+    assert_eq!(
+        book.best_bid(),
+        Some(PriceLevel {
+            price: 98.0,
+            size: 2.0
+        })
+    );
+}
+
+#[test]
+fn order_book_requires_snapshot_after_sequence_gap() {
+    let mut book = OrderBook::new(EXCHANGE.to_string(), SYMBOL.to_string());
+    let delta = book_update(OrderBookUpdateType::DELTA, 1, &[(99.0, 1.0)], &[], 100);
+    assert_eq!(book.apply(&delta), Err(OrderBookError::NoSnapshot));
+
+    let snapshot = book_update(
+        OrderBookUpdateType::SNAPSHOT,
+        5,
+        &[(99.0, 1.0)],
+        &[(100.0, 1.0)],
+        100,
+    );
+    book.apply(&snapshot).unwrap();
+
+    let delta = book_update(OrderBookUpdateType::DELTA, 7, &[(99.0, 2.0)], &[], 110);
+    assert_eq!(
+        book.apply(&delta),
+        Err(OrderBookError::SequenceGap {
+            expected: 6,
+            received: 7
+        })
+    );
+    assert!(!book.is_synced());
+    assert_eq!(book.best_bid(), None);
+
+    let delta = book_update(OrderBookUpdateType::DELTA, 8, &[(99.0, 2.0)], &[], 120);
+    assert_eq!(book.apply(&delta), Err(OrderBookError::NoSnapshot));
+
+    let mut other = snapshot.clone();
+    other.symbol = "other_symbol".to_string();
+    assert_eq!(book.apply(&other), Err(OrderBookError::WrongInstrument));
+    // updates of other instruments are skipped by on_event
+    assert_eq!(book.on_event(&Event::NewOrderBookUpdate(other)), Ok(()));
+}
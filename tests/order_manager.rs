@@ -0,0 +1,190 @@
+use crossbeam_channel::unbounded;
+use geger::core::events::Event;
+use geger::core::gateway_router::{CancelOrderRequest, ExchangeRequest, NewOrderRequest};
+use geger::core::market_data::{MarketDataEvent, Quote};
+use geger::core::order_manager::OrderManager;
+use geger::core::types::{OrderStatus, OrderType, Side, TimeInForce, Timestamp};
+use geger::sim::broker::{SimBroker, SimBrokerConfig};
+use geger::sim::environment::SimulatedBroker;
+
+const EXCHANGE: &str = "test_exchange";
+const SYMBOL: &str = "test_symbol";
+
+fn quote(bid: f64, ask: f64, ts: Timestamp) -> MarketDataEvent {
+    MarketDataEvent::NewQuote(Quote {
+        event_id: None,
+        symbol: SYMBOL.to_string(),
+        exchange: EXCHANGE.to_string(),
+        bid,
+        ask,
+        bid_size: None,
+        ask_size: None,
+        exchange_timestamp: ts,
+        received_timestamp: ts,
+    })
+}
+
+fn limit_order_request(
+    client_order_id: &str,
+    side: Side,
+    price: f64,
+    creation_ts: Timestamp,
+) -> NewOrderRequest {
+    NewOrderRequest {
+        request_id: client_order_id.to_string(),
+        client_order_id: client_order_id.to_string(),
+        exchange: EXCHANGE.to_string(),
+        r#type: OrderType::LIMIT,
+        time_in_force: TimeInForce::GTC,
+        price: Some(price),
+        trigger_price: None,
+        symbol: SYMBOL.to_string(),
+        quantity: 1.0,
+        side,
+        creation_ts,
+    }
+}
+
+fn apply(order_manager: &mut OrderManager, mut events: Vec<Event>) {
+    events.sort_by_key(|e| e.timestamp());
+    for event in &events {
+        order_manager.on_event(event);
+    }
+}
+
+#[test]
+fn order_manager_tracks_order_lifecycle() {
+    let (sender, receiver) = unbounded();
+    let mut broker = SimBroker::new(EXCHANGE.to_string(), receiver, SimBrokerConfig::default());
+    let mut order_manager = OrderManager::new();
+
+    for request in [
+        limit_order_request("1", Side::BUY, 90.0, 100),
+        limit_order_request("2", Side::SELL, 100.0, 100),
+    ] {
+        order_manager.on_new_order_request(&request);
+        sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+    }
+    assert_eq!(
+        order_manager.order("1").unwrap().status(),
+        &OrderStatus::PENDING_NEW
+    );
+    assert_eq!(
+        order_manager
+            .open_orders_by_symbol(EXCHANGE, SYMBOL)
+            .count(),
+        2
+    );
+
+    apply(
+        &mut order_manager,
+        broker.on_new_market_data(&quote(95.0, 99.0, 100)),
+    );
+    let order = order_manager.order("1").unwrap();
+    assert_eq!(order.status(), &OrderStatus::NEW);
+    assert_eq!(order.exchange_order_id(), Some(&"1".to_string()));
+
+    apply(
+        &mut order_manager,
+        broker.on_new_market_data(&quote(101.0, 102.0, 110)),
+    );
+    let order = order_manager.order("2").unwrap();
+    assert_eq!(order.status(), &OrderStatus::FILLED);
+    assert_eq!(order.avg_fill_price(), Some(100.0));
+
+    let cancel = CancelOrderRequest {
+        request_id: "cancel_1".to_string(),
+        client_order_id: None,
+        exchange_order_id: Some("1".to_string()),
+        exchange: EXCHANGE.to_string(),
+        symbol: SYMBOL.to_string(),
+        creation_ts: 120,
+    };
+    order_manager.on_cancel_order_request(&cancel);
+    sender.send(ExchangeRequest::CancelOrder(cancel)).unwrap();
+    assert_eq!(
+        order_manager.order("1").unwrap().status(),
+        &OrderStatus::PENDING_CANCEL
+    );
+
+    apply(
+        &mut order_manager,
+        broker.on_new_market_data(&quote(101.0, 102.0, 120)),
+    );
+    assert_eq!(
+        order_manager.order("1").unwrap().status(),
+        &OrderStatus::CANCELED
+    );
+    assert_eq!(order_manager.open_orders().count(), 0);
+    assert_eq!(order_manager.remove_done_orders().len(), 2);
+    assert_eq!(order_manager.orders().count(), 0);
+}
+
+#[test]
+fn order_manager_restores_status_on_cancel_rejected() {
+    let (sender, receiver) = unbounded();
+    let config = SimBrokerConfig::new(false, Some(10), Some(5));
+    let mut broker = SimBroker::new(EXCHANGE.to_string(), receiver, config);
+    let mut order_manager = OrderManager::new();
+
+    let request = limit_order_request("1", Side::BUY, 90.0, 100);
+    order_manager.on_new_order_request(&request);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+    apply(
+        &mut order_manager,
+        broker.on_new_market_data(&quote(95.0, 99.0, 100)),
+    );
+    apply(
+        &mut order_manager,
+        broker.on_new_market_data(&quote(95.0, 99.0, 150)),
+    );
+    assert_eq!(
+        order_manager.order("1").unwrap().status(),
+        &OrderStatus::NEW
+    );
+
+    let cancel = CancelOrderRequest {
+        request_id: "cancel_1".to_string(),
+        client_order_id: Some("1".to_string()),
+        exchange_order_id: Some("unknown".to_string()),
+        exchange: EXCHANGE.to_string(),
+        symbol: SYMBOL.to_string(),
+        creation_ts: 150,
+    };
+    order_manager.on_cancel_order_request(&cancel);
+    sender.send(ExchangeRequest::CancelOrder(cancel)).unwrap();
+    apply(
+        &mut order_manager,
+        broker.on_new_market_data(&quote(95.0, 99.0, 200)),
+    );
+    assert_eq!(
+        order_manager.order("1").unwrap().status(),
+        &OrderStatus::NEW
+    );
+    assert_eq!(order_manager.open_orders_by_exchange(EXCHANGE).count(), 1);
+}
+
+#[test]
+fn order_manager_tracks_unsupported_order_type_until_rejected() {
+    let (sender, receiver) = unbounded();
+    let mut broker = SimBroker::new(EXCHANGE.to_string(), receiver, SimBrokerConfig::default());
+    let mut order_manager = OrderManager::new();
+
+    let mut request = limit_order_request("1", Side::SELL, 100.0, 100);
+    request.r#type = OrderType::LIQUIDATION;
+    order_manager.on_new_order_request(&request);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+    let order = order_manager.order("1").unwrap();
+    assert_eq!(order.status(), &OrderStatus::PENDING_NEW);
+    assert_eq!(order.order_type(), &OrderType::LIQUIDATION);
+
+    apply(
+        &mut order_manager,
+        broker.on_new_market_data(&quote(95.0, 99.0, 100)),
+    );
+    assert_eq!(
+        order_manager.order("1").unwrap().status(),
+        &OrderStatus::REJECTED
+    );
+    assert_eq!(order_manager.open_orders().count(), 0);
+}
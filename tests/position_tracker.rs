@@ -0,0 +1,113 @@
+use crossbeam_channel::unbounded;
+use geger::core::gateway_router::{ExchangeRequest, NewOrderRequest};
+use geger::core::market_data::{MarketDataEvent, Quote, Trade};
+use geger::core::position_tracker::PositionTracker;
+use geger::core::types::{OrderType, Side, TimeInForce, Timestamp};
+use geger::sim::broker::{SimBroker, SimBrokerConfig};
+use geger::sim::environment::SimulatedBroker;
+use geger::sim::fees::FeeSchedule;
+
+const EXCHANGE: &str = "test_exchange";
+const SYMBOL: &str = "test_symbol";
+
+fn quote(bid: f64, ask: f64, ts: Timestamp) -> MarketDataEvent {
+    MarketDataEvent::NewQuote(Quote {
+        event_id: None,
+        symbol: SYMBOL.to_string(),
+        exchange: EXCHANGE.to_string(),
+        bid,
+        ask,
+        bid_size: None,
+        ask_size: None,
+        exchange_timestamp: ts,
+        received_timestamp: ts,
+    })
+}
+
+fn trade(price: f64, ts: Timestamp) -> MarketDataEvent {
+    MarketDataEvent::NewMarketTrade(Trade {
+        event_id: None,
+        symbol: SYMBOL.to_string(),
+        exchange: EXCHANGE.to_string(),
+        last_price: price,
+        last_size: 1.0,
+        exchange_timestamp: ts,
+        received_timestamp: ts,
+    })
+}
+
+fn market_order_request(
+    client_order_id: &str,
+    side: Side,
+    quantity: f64,
+    creation_ts: Timestamp,
+) -> NewOrderRequest {
+    NewOrderRequest {
+        request_id: client_order_id.to_string(),
+        client_order_id: client_order_id.to_string(),
+        exchange: EXCHANGE.to_string(),
+        r#type: OrderType::MARKET,
+        time_in_force: TimeInForce::GTC,
+        price: None,
+        trigger_price: None,
+        symbol: SYMBOL.to_string(),
+        quantity,
+        side,
+        creation_ts,
+    }
+}
+
+fn step(broker: &mut SimBroker, position_tracker: &mut PositionTracker, md: MarketDataEvent) {
+    let mut events = broker.on_new_market_data(&md);
+    events.push(md.into());
+    events.sort_by_key(|e| e.timestamp());
+    for event in &events {
+        position_tracker.on_event(event);
+    }
+}
+
+#[test]
+fn position_tracker_accumulates_fills_and_marks_to_market() {
+    let (sender, receiver) = unbounded();
+    let config =
+        SimBrokerConfig::default().with_fee_schedule(FeeSchedule::new(0.0, 0.001, 0.0, None));
+    let mut broker = SimBroker::new(EXCHANGE.to_string(), receiver, config);
+    let mut position_tracker = PositionTracker::new();
+    step(&mut broker, &mut position_tracker, quote(99.0, 100.0, 100));
+
+    sender
+        .send(ExchangeRequest::NewOrder(market_order_request(
+            "1",
+            Side::BUY,
+            2.0,
+            100,
+        )))
+        .unwrap();
+    step(&mut broker, &mut position_tracker, quote(99.0, 101.0, 110));
+    step(&mut broker, &mut position_tracker, quote(102.0, 104.0, 120));
+    let position = position_tracker.position(EXCHANGE, SYMBOL).unwrap();
+    assert_eq!(position.quantity, 2.0);
+    assert_eq!(position.avg_entry_price, Some(101.0));
+    assert_eq!(position.unrealized_pnl, 4.0);
+    assert_eq!(position.realized_pnl, 0.0);
+
+    // sell flips position from 2.0 long to 1.0 short
+    sender
+        .send(ExchangeRequest::NewOrder(market_order_request(
+            "2",
+            Side::SELL,
+            3.0,
+            120,
+        )))
+        .unwrap();
+    step(&mut broker, &mut position_tracker, quote(104.0, 105.0, 130));
+    step(&mut broker, &mut position_tracker, trade(100.0, 140));
+    let position = position_tracker.position(EXCHANGE, SYMBOL).unwrap();
+    assert_eq!(position.quantity, -1.0);
+    assert_eq!(position.avg_entry_price, Some(104.0));
+    assert_eq!(position.realized_pnl, 6.0);
+    assert_eq!(position.unrealized_pnl, 4.0);
+    assert!((position.commission - (2.0 * 101.0 + 3.0 * 104.0) * 0.001).abs() < 1e-9);
+    assert_eq!(position_tracker.realized_pnl(), 6.0);
+    assert_eq!(position_tracker.unrealized_pnl(), 4.0);
+}
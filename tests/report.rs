@@ -0,0 +1,228 @@
+use crossbeam_channel::unbounded;
+use geger::core::actions_context::ActionsContext;
+use geger::core::engine::Engine;
+use geger::core::event_loop::{Actor, StopHandle};
+use geger::core::events::Event;
+use geger::core::gateway_router::{ExchangeRequest, NewOrderRequest};
+use geger::core::market_data::{MarketDataEvent, Quote};
+use geger::core::message_bus::{CrossbeamMessageSender, LoggerMessageHandler, SimpleMessage};
+use geger::core::types::{OrderType, Side, TimeInForce, Timestamp};
+use geger::sim::broker::{SimBroker, SimBrokerConfig};
+use geger::sim::environment::{SimulatedBroker, SimulatedTradingMarketDataProvider};
+use geger::sim::report::{BacktestReport, ReportCollector};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+const EXCHANGE: &str = "test_exchange";
+const SYMBOL: &str = "test_symbol";
+
+fn quote(bid: f64, ask: f64, ts: Timestamp) -> MarketDataEvent {
+    MarketDataEvent::NewQuote(Quote {
+        event_id: None,
+        symbol: SYMBOL.to_string(),
+        exchange: EXCHANGE.to_string(),
+        bid,
+        ask,
+        bid_size: None,
+        ask_size: None,
+        exchange_timestamp: ts,
+        received_timestamp: ts,
+    })
+}
+
+fn market_order_request(
+    client_order_id: &str,
+    side: Side,
+    creation_ts: Timestamp,
+) -> NewOrderRequest {
+    NewOrderRequest {
+        request_id: client_order_id.to_string(),
+        client_order_id: client_order_id.to_string(),
+        exchange: EXCHANGE.to_string(),
+        r#type: OrderType::MARKET,
+        time_in_force: TimeInForce::GTC,
+        price: None,
+        trigger_price: None,
+        symbol: SYMBOL.to_string(),
+        quantity: 1.0,
+        side,
+        creation_ts,
+    }
+}
+
+fn run_sim() -> BacktestReport {
+    let (sender, receiver) = unbounded();
+    let mut broker = SimBroker::new(EXCHANGE.to_string(), receiver, SimBrokerConfig::default());
+    let mut collector = ReportCollector::new(1000.0);
+    let mut step = |md: MarketDataEvent| {
+        let mut events = broker.on_new_market_data(&md);
+        events.sort_by_key(|e| e.timestamp());
+        for event in &events {
+            collector.on_event(event);
+        }
+    };
+
+    step(quote(99.0, 100.0, 100));
+    sender
+        .send(ExchangeRequest::NewOrder(market_order_request(
+            "1",
+            Side::BUY,
+            100,
+        )))
+        .unwrap();
+    step(quote(99.0, 101.0, 110));
+    step(quote(102.0, 104.0, 120));
+    step(quote(96.0, 98.0, 130));
+    sender
+        .send(ExchangeRequest::NewOrder(market_order_request(
+            "2",
+            Side::SELL,
+            130,
+        )))
+        .unwrap();
+    step(quote(104.0, 105.0, 140));
+    sender
+        .send(ExchangeRequest::NewOrder(market_order_request(
+            "3",
+            Side::BUY,
+            140,
+        )))
+        .unwrap();
+    step(quote(104.0, 105.0, 150));
+    sender
+        .send(ExchangeRequest::NewOrder(market_order_request(
+            "4",
+            Side::SELL,
+            150,
+        )))
+        .unwrap();
+    step(quote(103.0, 104.0, 160));
+    collector.report()
+}
+
+struct VecMDProvider {
+    events: VecDeque<MarketDataEvent>,
+}
+
+impl SimulatedTradingMarketDataProvider for VecMDProvider {
+    fn next_event(&mut self) -> Option<MarketDataEvent> {
+        self.events.pop_front()
+    }
+}
+
+/// Sends the same orders as `run_sim` and collects report from events it sees
+struct ReportingStrategy {
+    collector: ReportCollector,
+    orders: HashMap<Timestamp, (&'static str, Side)>,
+}
+
+impl Actor<SimpleMessage, CrossbeamMessageSender<SimpleMessage>> for ReportingStrategy {
+    fn on_event(
+        &mut self,
+        event: &Event,
+        actions_context: &mut ActionsContext<SimpleMessage, CrossbeamMessageSender<SimpleMessage>>,
+    ) {
+        self.collector.on_event(event);
+        if let Event::NewQuote(quote) = event {
+            if let Some((client_order_id, side)) = self.orders.remove(&quote.exchange_timestamp) {
+                let request = market_order_request(client_order_id, side, 0);
+                actions_context.send_order(request).unwrap();
+            }
+        }
+    }
+}
+
+fn run_engine_sim() -> BacktestReport {
+    let quotes = [
+        (99.0, 100.0, 100),
+        (99.0, 101.0, 110),
+        (102.0, 104.0, 120),
+        (96.0, 98.0, 130),
+        (104.0, 105.0, 140),
+        (104.0, 105.0, 150),
+        (103.0, 104.0, 160),
+    ];
+    let md_provider = VecMDProvider {
+        events: quotes
+            .iter()
+            .map(|&(bid, ask, ts)| quote(bid, ask, ts))
+            .collect(),
+    };
+    let strategy = Arc::new(Mutex::new(ReportingStrategy {
+        collector: ReportCollector::new(1000.0),
+        orders: HashMap::from([
+            (100, ("1", Side::BUY)),
+            (130, ("2", Side::SELL)),
+            (140, ("3", Side::BUY)),
+            (150, ("4", Side::SELL)),
+        ]),
+    }));
+
+    let mut engine: Engine<
+        ReportingStrategy,
+        SimpleMessage,
+        CrossbeamMessageSender<SimpleMessage>,
+        LoggerMessageHandler,
+    > = Engine::new();
+    engine.add_exchange(EXCHANGE.to_string());
+    engine.add_actor(strategy.clone());
+    let result = engine
+        .run_with_sim_environment(md_provider, None, HashMap::new(), false, StopHandle::new())
+        .unwrap();
+    assert!(result.errors.is_empty());
+
+    let report = strategy.lock().unwrap().collector.report();
+    report
+}
+
+#[test]
+fn report_computes_summary_statistics() {
+    let report = run_sim();
+    let summary = &report.summary;
+    assert_eq!(summary.fills, 4);
+    assert_eq!(summary.final_equity, 1001.0);
+    assert!((summary.total_return - 0.001).abs() < 1e-12);
+    assert!((summary.turnover - (101.0 + 104.0 + 105.0 + 103.0) / 1000.0).abs() < 1e-12);
+    assert_eq!(summary.win_rate, Some(0.5));
+    // equity went from 1002.0 at 120 down to 996.0 at 130
+    assert!((summary.max_drawdown - 6.0 / 1002.0).abs() < 1e-12);
+    assert!(summary.sharpe_ratio.is_some());
+    assert!(summary.sortino_ratio.is_some());
+
+    assert_eq!(report.symbols.len(), 1);
+    assert_eq!(report.symbols[0].realized_pnl, 1.0);
+    assert_eq!(report.symbols[0].fills, 4);
+    assert_eq!(report.equity_curve.len(), 7);
+}
+
+#[test]
+fn report_written_as_json_and_csv() {
+    let report = run_sim();
+    let dir = std::env::temp_dir().join(format!("geger_report_{}", std::process::id()));
+    let json_path = dir.join("report.json");
+    report.write_csv(&dir).unwrap();
+    report.write_json(&json_path).unwrap();
+
+    let loaded: BacktestReport =
+        serde_json::from_reader(std::fs::File::open(&json_path).unwrap()).unwrap();
+    assert_eq!(loaded.fills, report.fills);
+    assert_eq!(loaded.equity_curve, report.equity_curve);
+    assert_eq!(loaded.summary.win_rate, report.summary.win_rate);
+
+    let fills_csv = std::fs::read_to_string(dir.join("fills.csv")).unwrap();
+    assert_eq!(fills_csv.lines().count(), 5);
+    let summary_csv = std::fs::read_to_string(dir.join("summary.csv")).unwrap();
+    assert!(summary_csv.starts_with("initial_capital,final_equity,total_return"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn report_collected_in_engine_sim_run() {
+    let report = run_engine_sim();
+    let expected = run_sim();
+    assert_eq!(report.fills, expected.fills);
+    assert_eq!(report.summary.fills, 4);
+    assert_eq!(report.summary.final_equity, expected.summary.final_equity);
+    assert_eq!(report.summary.win_rate, expected.summary.win_rate);
+    assert_eq!(report.symbols[0].realized_pnl, 1.0);
+}
@@ -0,0 +1,1154 @@
+use crossbeam_channel::{unbounded, Sender};
+use geger::core::events::{Event, OrderUpdate};
+use geger::core::gateway_router::{
+    AmendOrderRequest, CancelAllRequest, CancelOrderRequest, ExchangeRequest, NewOrderRequest,
+};
+use geger::core::market_data::{
+    Bar, MarketDataEvent, OrderBookUpdate, OrderBookUpdateType, PriceLevel, Quote, Trade,
+};
+use geger::core::types::{ExecutionType, OrderStatus, OrderType, Side, TimeInForce, Timestamp};
+use geger::sim::broker::{
+    ExecutionModel, FillModel, IntrabarPath, QueueModel, SimBroker, SimBrokerConfig,
+};
+use geger::sim::environment::SimulatedBroker;
+use geger::sim::fees::FeeSchedule;
+use geger::sim::latency::{EmpiricalLatency, LogNormalLatency, UniformLatency};
+use geger::sim::slippage::{FixedBpsSlippage, SpreadFractionSlippage};
+use std::sync::Arc;
+
+const EXCHANGE: &str = "test_exchange";
+const SYMBOL: &str = "test_symbol";
+
+fn new_broker(config: SimBrokerConfig) -> (SimBroker, Sender<ExchangeRequest>) {
+    let (sender, receiver) = unbounded();
+    (
+        SimBroker::new(EXCHANGE.to_string(), receiver, config),
+        sender,
+    )
+}
+
+fn quote(bid: f64, ask: f64, ts: Timestamp) -> MarketDataEvent {
+    MarketDataEvent::NewQuote(Quote {
+        event_id: None,
+        symbol: SYMBOL.to_string(),
+        exchange: EXCHANGE.to_string(),
+        bid,
+        ask,
+        bid_size: None,
+        ask_size: None,
+        exchange_timestamp: ts,
+        received_timestamp: ts,
+    })
+}
+
+fn quote_with_size(
+    bid: f64,
+    bid_size: f64,
+    ask: f64,
+    ask_size: f64,
+    ts: Timestamp,
+) -> MarketDataEvent {
+    MarketDataEvent::NewQuote(Quote {
+        event_id: None,
+        symbol: SYMBOL.to_string(),
+        exchange: EXCHANGE.to_string(),
+        bid,
+        ask,
+        bid_size: Some(bid_size),
+        ask_size: Some(ask_size),
+        exchange_timestamp: ts,
+        received_timestamp: ts,
+    })
+}
+
+fn trade(price: f64, size: f64, ts: Timestamp) -> MarketDataEvent {
+    MarketDataEvent::NewMarketTrade(Trade {
+        event_id: None,
+        symbol: SYMBOL.to_string(),
+        exchange: EXCHANGE.to_string(),
+        last_price: price,
+        last_size: size,
+        exchange_timestamp: ts,
+        received_timestamp: ts,
+    })
+}
+
+fn bar(open: f64, high: f64, low: f64, close: f64, ts: Timestamp) -> MarketDataEvent {
+    MarketDataEvent::NewBar(Bar {
+        event_id: None,
+        symbol: SYMBOL.to_string(),
+        exchange: EXCHANGE.to_string(),
+        bar_type: None,
+        open,
+        high,
+        low,
+        close,
+        volume: 100.0,
+        vwap: None,
+        trade_count: 0,
+        start_timestamp: ts - 100,
+        exchange_timestamp: ts,
+        received_timestamp: ts,
+    })
+}
+
+fn book_update(
+    update_type: OrderBookUpdateType,
+    sequence: u64,
+    bids: &[(f64, f64)],
+    asks: &[(f64, f64)],
+    ts: Timestamp,
+) -> MarketDataEvent {
+    let levels = |levels: &[(f64, f64)]| {
+        levels
+            .iter()
+            .map(|&(price, size)| PriceLevel { price, size })
+            .collect()
+    };
+    MarketDataEvent::NewOrderBookUpdate(OrderBookUpdate {
+        event_id: None,
+        symbol: SYMBOL.to_string(),
+        exchange: EXCHANGE.to_string(),
+        update_type,
+        sequence,
+        bids: levels(bids),
+        asks: levels(asks),
+        exchange_timestamp: ts,
+        received_timestamp: ts,
+    })
+}
+
+fn new_order_request(
+    client_order_id: &str,
+    r#type: OrderType,
+    side: Side,
+    price: Option<f64>,
+    quantity: f64,
+    creation_ts: Timestamp,
+) -> NewOrderRequest {
+    NewOrderRequest {
+        request_id: client_order_id.to_string(),
+        client_order_id: client_order_id.to_string(),
+        exchange: EXCHANGE.to_string(),
+        r#type,
+        time_in_force: TimeInForce::GTC,
+        price,
+        trigger_price: None,
+        symbol: SYMBOL.to_string(),
+        quantity,
+        side,
+        creation_ts,
+    }
+}
+
+fn amend_order_request(
+    exchange_order_id: &str,
+    new_price: Option<f64>,
+    new_quantity: Option<f64>,
+    creation_ts: Timestamp,
+) -> AmendOrderRequest {
+    AmendOrderRequest {
+        request_id: format!("amend_{}", exchange_order_id),
+        client_order_id: exchange_order_id.to_string(),
+        exchange_order_id: exchange_order_id.to_string(),
+        exchange: EXCHANGE.to_string(),
+        symbol: SYMBOL.to_string(),
+        new_price,
+        new_quantity,
+        creation_ts,
+    }
+}
+
+fn order_updates(events: &[Event]) -> Vec<OrderUpdate> {
+    events
+        .iter()
+        .filter_map(|e| match e {
+            Event::UDSOrderUpdate(u) => Some(u.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn fills(events: &[Event]) -> Vec<OrderUpdate> {
+    order_updates(events)
+        .into_iter()
+        .filter(|u| u.execution_type == ExecutionType::TRADE)
+        .collect()
+}
+
+#[test]
+fn market_order_filled_on_next_quote_opposite_side() {
+    let (mut broker, sender) = new_broker(SimBrokerConfig::new(false, Some(10), Some(5)));
+    broker.on_new_market_data(&quote(99.0, 101.0, 100));
+
+    let request = new_order_request("1", OrderType::MARKET, Side::BUY, None, 2.0, 100);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+
+    // request is acked at 110 and order is created at 115, so quote at 112 can't fill it
+    let events = broker.on_new_market_data(&quote(100.0, 102.0, 112));
+    assert!(fills(&events).is_empty());
+    assert_eq!(order_updates(&events)[0].order_status, OrderStatus::NEW);
+
+    let events = broker.on_new_market_data(&quote(101.0, 103.0, 120));
+    let fills = fills(&events);
+    assert_eq!(fills.len(), 1);
+    assert_eq!(fills[0].order_status, OrderStatus::FILLED);
+    assert_eq!(fills[0].last_filled_price, Some(103.0));
+    assert_eq!(fills[0].average_price, Some(103.0));
+    assert_eq!(fills[0].accumulated_filled_qty, Some(2.0));
+    assert_eq!(fills[0].original_price, None);
+}
+
+#[test]
+fn market_order_filled_on_next_trade_price() {
+    let (mut broker, sender) = new_broker(SimBrokerConfig::default());
+    let request = new_order_request("1", OrderType::MARKET, Side::SELL, None, 1.0, 100);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+
+    let events = broker.on_new_market_data(&trade(98.5, 3.0, 100));
+    let fills = fills(&events);
+    assert_eq!(fills.len(), 1);
+    assert_eq!(fills[0].last_filled_price, Some(98.5));
+    assert_eq!(fills[0].side, Side::SELL);
+}
+
+#[test]
+fn stop_order_triggered_and_executed_as_market() {
+    let (mut broker, sender) = new_broker(SimBrokerConfig::default());
+    let mut request = new_order_request("1", OrderType::STOP, Side::SELL, None, 1.0, 100);
+    request.trigger_price = Some(95.0);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+
+    let events = broker.on_new_market_data(&quote(96.0, 97.0, 100));
+    assert!(fills(&events).is_empty());
+    assert_eq!(order_updates(&events)[0].stop_price, Some(95.0));
+
+    let events = broker.on_new_market_data(&trade(94.5, 1.0, 110));
+    let updates = order_updates(&events);
+    assert_eq!(updates.len(), 2);
+    assert_eq!(updates[0].execution_type, ExecutionType::NEW);
+    assert_eq!(updates[0].order_type, Some(OrderType::MARKET));
+    assert_eq!(updates[0].stop_price, Some(95.0));
+    assert_eq!(updates[1].order_status, OrderStatus::FILLED);
+    assert_eq!(updates[1].last_filled_price, Some(94.5));
+    assert_eq!(updates[1].stop_price, Some(95.0));
+}
+
+#[test]
+fn stop_limit_order_rests_as_limit_after_trigger() {
+    let (mut broker, sender) = new_broker(SimBrokerConfig::default());
+    let mut request =
+        new_order_request("1", OrderType::STOP_LIMIT, Side::BUY, Some(105.0), 1.0, 100);
+    request.trigger_price = Some(104.0);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+    broker.on_new_market_data(&quote(102.0, 103.0, 100));
+
+    let events = broker.on_new_market_data(&quote(104.0, 106.0, 110));
+    let updates = order_updates(&events);
+    assert_eq!(updates.len(), 1);
+    assert_eq!(updates[0].order_type, Some(OrderType::LIMIT));
+
+    let events = broker.on_new_market_data(&quote(104.0, 105.0, 120));
+    let fills = fills(&events);
+    assert_eq!(fills.len(), 1);
+    assert_eq!(fills[0].last_filled_price, Some(105.0));
+}
+
+#[test]
+fn stop_order_without_trigger_price_rejected() {
+    let (mut broker, sender) = new_broker(SimBrokerConfig::default());
+    let request = new_order_request("1", OrderType::STOP, Side::SELL, None, 1.0, 100);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+
+    let events = broker.on_new_market_data(&quote(96.0, 97.0, 100));
+    let rejected: Vec<&Event> = events
+        .iter()
+        .filter(|e| matches!(e, Event::ResponseNewOrderRejected(_)))
+        .collect();
+    assert_eq!(rejected.len(), 1);
+    assert!(order_updates(&events).is_empty());
+}
+
+#[test]
+fn partial_fills_capped_by_available_size() {
+    let config = SimBrokerConfig::default().with_fill_model(FillModel::AvailableSize);
+    let (mut broker, sender) = new_broker(config);
+    let request = new_order_request("1", OrderType::MARKET, Side::BUY, None, 5.0, 100);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+
+    let events = broker.on_new_market_data(&quote_with_size(99.0, 10.0, 100.0, 2.0, 100));
+    let first = fills(&events);
+    assert_eq!(first.len(), 1);
+    assert_eq!(first[0].order_status, OrderStatus::PARTIALLY_FILLED);
+    assert_eq!(first[0].last_filled_qty, Some(2.0));
+    assert_eq!(first[0].accumulated_filled_qty, Some(2.0));
+
+    let events = broker.on_new_market_data(&trade(103.0, 3.0, 110));
+    let second = fills(&events);
+    assert_eq!(second.len(), 1);
+    assert_eq!(second[0].order_status, OrderStatus::FILLED);
+    assert_eq!(second[0].last_filled_qty, Some(3.0));
+    assert_eq!(second[0].accumulated_filled_qty, Some(5.0));
+    assert_eq!(second[0].average_price, Some(101.8));
+
+    let events = broker.on_new_market_data(&trade(103.0, 3.0, 120));
+    assert!(fills(&events).is_empty());
+}
+
+#[test]
+fn orders_share_available_size_of_md_event() {
+    let config = SimBrokerConfig::default().with_fill_model(FillModel::AvailableSize);
+    let (mut broker, sender) = new_broker(config);
+    for client_order_id in ["1", "2", "3"] {
+        let request = new_order_request(
+            client_order_id,
+            OrderType::MARKET,
+            Side::BUY,
+            None,
+            3.0,
+            100,
+        );
+        sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+    }
+    let request = new_order_request("4", OrderType::MARKET, Side::SELL, None, 3.0, 100);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+
+    let events = broker.on_new_market_data(&quote_with_size(99.0, 10.0, 100.0, 4.0, 100));
+    // all orders are accepted before md event executes them
+    let statuses: Vec<(Option<String>, ExecutionType)> = order_updates(&events)
+        .into_iter()
+        .map(|u| (u.client_order_id, u.execution_type))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![
+            (Some("1".to_string()), ExecutionType::NEW),
+            (Some("2".to_string()), ExecutionType::NEW),
+            (Some("3".to_string()), ExecutionType::NEW),
+            (Some("4".to_string()), ExecutionType::NEW),
+            (Some("1".to_string()), ExecutionType::TRADE),
+            (Some("2".to_string()), ExecutionType::TRADE),
+            (Some("4".to_string()), ExecutionType::TRADE),
+        ]
+    );
+    let filled: Vec<(Option<String>, Option<f64>)> = fills(&events)
+        .into_iter()
+        .map(|u| (u.client_order_id, u.last_filled_qty))
+        .collect();
+    assert_eq!(
+        filled,
+        vec![
+            (Some("1".to_string()), Some(3.0)),
+            (Some("2".to_string()), Some(1.0)),
+            (Some("4".to_string()), Some(3.0)),
+        ]
+    );
+
+    // the next event has its own size
+    let events = broker.on_new_market_data(&trade(100.0, 2.0, 110));
+    let filled: Vec<(Option<String>, Option<f64>)> = fills(&events)
+        .into_iter()
+        .map(|u| (u.client_order_id, u.last_filled_qty))
+        .collect();
+    assert_eq!(filled, vec![(Some("2".to_string()), Some(2.0)),]);
+}
+
+#[test]
+fn quote_without_size_does_not_cap_fills() {
+    let config = SimBrokerConfig::default().with_fill_model(FillModel::AvailableSize);
+    let (mut broker, sender) = new_broker(config);
+    let request = new_order_request("1", OrderType::MARKET, Side::BUY, None, 5.0, 100);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+
+    let events = broker.on_new_market_data(&quote(99.0, 100.0, 100));
+    let fills = fills(&events);
+    assert_eq!(fills.len(), 1);
+    assert_eq!(fills[0].last_filled_qty, Some(5.0));
+}
+
+#[test]
+fn full_quantity_fill_model_ignores_size() {
+    let (mut broker, sender) = new_broker(SimBrokerConfig::default());
+    let request = new_order_request("1", OrderType::LIMIT, Side::SELL, Some(100.0), 5.0, 100);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+
+    let events = broker.on_new_market_data(&quote_with_size(100.0, 1.0, 101.0, 1.0, 100));
+    let fills = fills(&events);
+    assert_eq!(fills.len(), 1);
+    assert_eq!(fills[0].order_status, OrderStatus::FILLED);
+    assert_eq!(fills[0].last_filled_qty, Some(5.0));
+}
+
+#[test]
+fn queued_limit_order_filled_after_displayed_size_traded() {
+    let config = SimBrokerConfig::default()
+        .with_fill_model(FillModel::AvailableSize)
+        .with_queue_model(QueueModel::DisplayedSize);
+    let (mut broker, sender) = new_broker(config);
+    let request = new_order_request("1", OrderType::LIMIT, Side::BUY, Some(99.0), 2.0, 100);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+
+    // order rests behind 5 lots displayed at 99.0, then 1 lot ahead is cancelled
+    broker.on_new_market_data(&quote_with_size(99.0, 5.0, 100.0, 1.0, 100));
+    broker.on_new_market_data(&quote_with_size(99.0, 4.0, 100.0, 1.0, 110));
+
+    let events = broker.on_new_market_data(&trade(99.0, 3.0, 120));
+    assert!(fills(&events).is_empty());
+
+    let events = broker.on_new_market_data(&trade(99.0, 2.0, 130));
+    let first = fills(&events);
+    assert_eq!(first.len(), 1);
+    assert_eq!(first[0].last_filled_qty, Some(1.0));
+    assert_eq!(first[0].order_status, OrderStatus::PARTIALLY_FILLED);
+
+    let events = broker.on_new_market_data(&trade(98.5, 1.0, 140));
+    let second = fills(&events);
+    assert_eq!(second.len(), 1);
+    assert_eq!(second[0].last_filled_price, Some(99.0));
+    assert_eq!(second[0].order_status, OrderStatus::FILLED);
+}
+
+#[test]
+fn queued_limit_order_at_top_of_book_has_empty_queue() {
+    let config = SimBrokerConfig::default().with_queue_model(QueueModel::DisplayedSize);
+    let (mut broker, sender) = new_broker(config);
+    let request = new_order_request("1", OrderType::LIMIT, Side::SELL, Some(100.5), 2.0, 100);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+
+    broker.on_new_market_data(&quote_with_size(99.0, 5.0, 101.0, 3.0, 100));
+    let events = broker.on_new_market_data(&trade(100.5, 0.5, 110));
+    let fills = fills(&events);
+    assert_eq!(fills.len(), 1);
+    assert_eq!(fills[0].order_status, OrderStatus::FILLED);
+}
+
+#[test]
+fn ioc_order_expires_unfilled_remainder() {
+    let config = SimBrokerConfig::default().with_fill_model(FillModel::AvailableSize);
+    let (mut broker, sender) = new_broker(config);
+    let mut request = new_order_request("1", OrderType::LIMIT, Side::BUY, Some(100.0), 5.0, 100);
+    request.time_in_force = TimeInForce::IOC;
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+
+    let events = broker.on_new_market_data(&quote_with_size(99.0, 1.0, 100.0, 2.0, 100));
+    let updates = order_updates(&events);
+    assert_eq!(updates.len(), 3);
+    assert_eq!(updates[1].order_status, OrderStatus::PARTIALLY_FILLED);
+    assert_eq!(updates[1].last_filled_qty, Some(2.0));
+    assert_eq!(updates[2].execution_type, ExecutionType::EXPIRED);
+    assert_eq!(updates[2].order_status, OrderStatus::EXPIRED);
+    assert_eq!(updates[2].accumulated_filled_qty, Some(2.0));
+
+    let events = broker.on_new_market_data(&quote_with_size(99.0, 1.0, 100.0, 2.0, 110));
+    assert!(order_updates(&events).is_empty());
+}
+
+#[test]
+fn fok_order_expires_when_size_is_not_available() {
+    let config = SimBrokerConfig::default().with_fill_model(FillModel::AvailableSize);
+    let (mut broker, sender) = new_broker(config);
+    let mut request = new_order_request("1", OrderType::MARKET, Side::SELL, None, 5.0, 100);
+    request.time_in_force = TimeInForce::FOK;
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+    let mut request = new_order_request("2", OrderType::MARKET, Side::SELL, None, 1.0, 100);
+    request.time_in_force = TimeInForce::FOK;
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+
+    let events = broker.on_new_market_data(&quote_with_size(99.0, 3.0, 100.0, 2.0, 100));
+    let updates: Vec<OrderUpdate> = order_updates(&events)
+        .into_iter()
+        .filter(|u| u.execution_type != ExecutionType::NEW)
+        .collect();
+    assert_eq!(updates.len(), 2);
+    for update in updates {
+        match update.client_order_id.as_deref() {
+            Some("1") => {
+                assert_eq!(update.order_status, OrderStatus::EXPIRED);
+                assert_eq!(update.accumulated_filled_qty, None);
+            }
+            Some("2") => assert_eq!(update.order_status, OrderStatus::FILLED),
+            other => unreachable!("{:?}", other),
+        }
+    }
+}
+
+#[test]
+fn gtx_order_expires_if_crossing_on_arrival() {
+    let (mut broker, sender) = new_broker(SimBrokerConfig::default());
+    let mut request = new_order_request("1", OrderType::LIMIT, Side::BUY, Some(100.0), 1.0, 100);
+    request.time_in_force = TimeInForce::GTX;
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+    let mut request = new_order_request("2", OrderType::LIMIT, Side::BUY, Some(99.0), 1.0, 100);
+    request.time_in_force = TimeInForce::GTX;
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+
+    let events = broker.on_new_market_data(&quote(99.0, 100.0, 100));
+    let expired: Vec<OrderUpdate> = order_updates(&events)
+        .into_iter()
+        .filter(|u| u.execution_type == ExecutionType::EXPIRED)
+        .collect();
+    assert_eq!(expired.len(), 1);
+    assert_eq!(expired[0].client_order_id, Some("1".to_string()));
+
+    // resting post only order is filled passively once market moves through it
+    let events = broker.on_new_market_data(&quote(98.0, 99.0, 110));
+    let fills = fills(&events);
+    assert_eq!(fills.len(), 1);
+    assert_eq!(fills[0].client_order_id, Some("2".to_string()));
+}
+
+#[test]
+fn gtx_order_checked_against_trade_on_trade_only_feed() {
+    let (mut broker, sender) = new_broker(SimBrokerConfig::default());
+    let mut request = new_order_request("1", OrderType::LIMIT, Side::BUY, Some(100.0), 1.0, 100);
+    request.time_in_force = TimeInForce::GTX;
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+    let mut request = new_order_request("2", OrderType::LIMIT, Side::BUY, Some(98.0), 1.0, 100);
+    request.time_in_force = TimeInForce::GTX;
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+
+    // trade through order price means that order would take liquidity
+    let events = broker.on_new_market_data(&trade(99.0, 1.0, 100));
+    let statuses: Vec<(Option<String>, ExecutionType)> = order_updates(&events)
+        .into_iter()
+        .map(|u| (u.client_order_id, u.execution_type))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![
+            (Some("1".to_string()), ExecutionType::NEW),
+            (Some("2".to_string()), ExecutionType::NEW),
+            (Some("1".to_string()), ExecutionType::EXPIRED),
+        ]
+    );
+
+    // trade at order price doesn't, so the other order rests and is filled passively
+    let events = broker.on_new_market_data(&trade(98.0, 1.0, 110));
+    let fills = fills(&events);
+    assert_eq!(fills.len(), 1);
+    assert_eq!(fills[0].client_order_id, Some("2".to_string()));
+    assert_eq!(fills[0].last_filled_price, Some(98.0));
+}
+
+#[test]
+fn fills_carry_maker_and_taker_commission() {
+    let fee_schedule = FeeSchedule::new(-0.0001, 0.0005, 0.5, Some("USDT".to_string()));
+    let config = SimBrokerConfig::default()
+        .with_fill_model(FillModel::AvailableSize)
+        .with_fee_schedule(fee_schedule);
+    let (mut broker, sender) = new_broker(config);
+    let request = new_order_request("1", OrderType::LIMIT, Side::BUY, Some(100.0), 2.0, 100);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+    let request = new_order_request("2", OrderType::MARKET, Side::SELL, None, 1.0, 100);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+
+    let events = broker.on_new_market_data(&quote(99.0, 101.0, 100));
+    let taker_fills = fills(&events);
+    assert_eq!(taker_fills.len(), 1);
+    assert_eq!(taker_fills[0].client_order_id, Some("2".to_string()));
+    assert_eq!(taker_fills[0].commission, Some(99.0 * 0.0005 + 0.5));
+    assert_eq!(taker_fills[0].commission_asset, Some("USDT".to_string()));
+
+    let events = broker.on_new_market_data(&trade(100.0, 1.0, 110));
+    let first = fills(&events);
+    assert_eq!(first[0].commission, Some(100.0 * -0.0001 + 0.5));
+
+    let events = broker.on_new_market_data(&trade(100.0, 1.0, 120));
+    let second = fills(&events);
+    assert_eq!(second[0].commission, Some(100.0 * -0.0001));
+}
+
+#[test]
+fn passive_order_filled_on_first_md_event_is_maker() {
+    let fee_schedule = FeeSchedule::new(-0.0001, 0.0005, 0.5, Some("USDT".to_string()));
+    let config = SimBrokerConfig::default()
+        .with_fee_schedule(fee_schedule)
+        .with_slippage_model(Arc::new(FixedBpsSlippage::new(10.0)));
+    let (mut broker, sender) = new_broker(config);
+    let request = new_order_request("1", OrderType::LIMIT, Side::BUY, Some(100.0), 1.0, 100);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+    let request = new_order_request("2", OrderType::LIMIT, Side::SELL, Some(99.5), 1.0, 100);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+
+    // trade at order price doesn't cross buy order, but goes through sell order
+    let events = broker.on_new_market_data(&trade(100.0, 1.0, 100));
+    let fills = fills(&events);
+    assert_eq!(fills.len(), 2);
+    assert_eq!(fills[0].client_order_id, Some("1".to_string()));
+    assert_eq!(fills[0].last_filled_price, Some(100.0));
+    assert_eq!(fills[0].commission, Some(100.0 * -0.0001 + 0.5));
+
+    assert_eq!(fills[1].client_order_id, Some("2".to_string()));
+    let taker_price = fills[1].last_filled_price.unwrap();
+    assert!(taker_price < 100.0);
+    assert_eq!(fills[1].commission, Some(taker_price * 0.0005 + 0.5));
+}
+
+#[test]
+fn slippage_applied_to_marketable_fills_only() {
+    let config =
+        SimBrokerConfig::default().with_slippage_model(Arc::new(SpreadFractionSlippage::new(0.5)));
+    let (mut broker, sender) = new_broker(config);
+    let request = new_order_request("1", OrderType::MARKET, Side::BUY, None, 1.0, 100);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+    let request = new_order_request("2", OrderType::LIMIT, Side::SELL, Some(103.0), 1.0, 100);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+
+    let events = broker.on_new_market_data(&quote(100.0, 102.0, 100));
+    let market_fills = fills(&events);
+    assert_eq!(market_fills.len(), 1);
+    assert_eq!(market_fills[0].last_filled_price, Some(103.0));
+
+    let events = broker.on_new_market_data(&quote(103.0, 104.0, 110));
+    let passive_fills = fills(&events);
+    assert_eq!(passive_fills.len(), 1);
+    assert_eq!(passive_fills[0].last_filled_price, Some(103.0));
+}
+
+#[test]
+fn slippage_does_not_exceed_limit_price() {
+    let config =
+        SimBrokerConfig::default().with_slippage_model(Arc::new(FixedBpsSlippage::new(10.0)));
+    let (mut broker, sender) = new_broker(config);
+    let request = new_order_request("1", OrderType::LIMIT, Side::SELL, Some(100.0), 1.0, 100);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+    let request = new_order_request("2", OrderType::MARKET, Side::SELL, None, 1.0, 100);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+
+    let events = broker.on_new_market_data(&quote(100.0, 101.0, 100));
+    for fill in fills(&events) {
+        match fill.client_order_id.as_deref() {
+            Some("1") => assert_eq!(fill.last_filled_price, Some(100.0)),
+            Some("2") => assert_eq!(fill.last_filled_price, Some(99.9)),
+            other => unreachable!("{:?}", other),
+        }
+    }
+}
+
+#[test]
+fn slippage_of_marketable_limit_order_applied_from_touch_price() {
+    let config =
+        SimBrokerConfig::default().with_slippage_model(Arc::new(FixedBpsSlippage::new(10.0)));
+    let (mut broker, sender) = new_broker(config);
+    let request = new_order_request("1", OrderType::LIMIT, Side::BUY, Some(102.0), 1.0, 100);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+    let request = new_order_request("2", OrderType::LIMIT, Side::BUY, Some(100.05), 1.0, 100);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+
+    let events = broker.on_new_market_data(&quote(99.0, 100.0, 100));
+    let fills = fills(&events);
+    assert_eq!(fills.len(), 2);
+    for fill in fills {
+        let price = fill.last_filled_price.unwrap();
+        match fill.client_order_id.as_deref() {
+            Some("1") => assert!((price - 100.1).abs() < 1e-9),
+            Some("2") => assert_eq!(price, 100.05),
+            other => unreachable!("{:?}", other),
+        }
+    }
+}
+
+#[test]
+fn marketable_limit_order_filled_at_touch_price() {
+    let (mut broker, sender) = new_broker(SimBrokerConfig::default());
+    let request = new_order_request("1", OrderType::LIMIT, Side::BUY, Some(100.0), 1.0, 100);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+    let request = new_order_request("2", OrderType::LIMIT, Side::SELL, Some(97.0), 1.0, 100);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+
+    let events = broker.on_new_market_data(&quote(98.0, 99.0, 100));
+    let prices: Vec<Option<f64>> = fills(&events)
+        .into_iter()
+        .map(|u| u.last_filled_price)
+        .collect();
+    assert_eq!(prices, vec![Some(99.0), Some(98.0)]);
+}
+
+fn latency_sample_run(seed: u64) -> (Vec<Timestamp>, Vec<Timestamp>) {
+    let config = SimBrokerConfig::default()
+        .with_wire_latency_model(Arc::new(LogNormalLatency::new(3.0, 1.0).unwrap()))
+        .with_internal_latency_model(Arc::new(UniformLatency::new(1, 20).unwrap()))
+        .with_seed(seed);
+    let (mut broker, sender) = new_broker(config);
+
+    let mut md_timestamps = vec![];
+    let mut user_stream_timestamps = vec![];
+    for i in 0..50 {
+        let md = quote(99.0, 101.0, i * 10);
+        md_timestamps.push(broker.sample_market_data_timestamp(&md));
+        let client_order_id = i.to_string();
+        let request = new_order_request(
+            &client_order_id,
+            OrderType::MARKET,
+            Side::BUY,
+            None,
+            1.0,
+            i * 10,
+        );
+        sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+        let events = broker.on_new_market_data(&md);
+        user_stream_timestamps.extend(order_updates(&events).iter().map(|u| u.timestamp));
+    }
+    (md_timestamps, user_stream_timestamps)
+}
+
+#[test]
+fn sampled_latencies_reproducible_for_seed() {
+    let first_run = latency_sample_run(42);
+    assert_eq!(first_run, latency_sample_run(42));
+    assert_ne!(first_run, latency_sample_run(43));
+}
+
+#[test]
+fn sampled_latencies_keep_streams_in_order() {
+    let (md_timestamps, user_stream_timestamps) = latency_sample_run(7);
+    assert!(md_timestamps.windows(2).all(|w| w[0] <= w[1]));
+    assert!(user_stream_timestamps.windows(2).all(|w| w[0] <= w[1]));
+
+    let histogram = EmpiricalLatency::new(vec![0, 10, 1000], vec![9, 1]).unwrap();
+    let config = SimBrokerConfig::default()
+        .with_wire_latency_model(Arc::new(histogram))
+        .with_seed(7);
+    let (mut broker, _) = new_broker(config);
+    let md_timestamps: Vec<Timestamp> = (0..100)
+        .map(|i| broker.sample_market_data_timestamp(&quote(99.0, 101.0, i * 10)))
+        .collect();
+    assert!(md_timestamps.windows(2).all(|w| w[0] <= w[1]));
+    assert!(md_timestamps.iter().all(|&ts| ts < 99 * 10 + 1000));
+}
+
+#[test]
+fn unstamped_market_data_stamped_by_broker() {
+    let (mut broker, _) = new_broker(SimBrokerConfig::new(false, Some(30), None));
+    let md_timestamps = |events: &[Event]| -> Vec<Timestamp> {
+        events
+            .iter()
+            .filter(|e| matches!(e, Event::NewQuote(_)))
+            .map(|e| e.timestamp())
+            .collect()
+    };
+
+    let events = broker.on_new_market_data(&quote(99.0, 101.0, 100));
+    assert_eq!(md_timestamps(&events), vec![130]);
+
+    // stamped by sim environment already
+    let mut md = quote(99.0, 101.0, 110);
+    let received_ts = broker.sample_market_data_timestamp(&md);
+    md.set_timestamp(received_ts);
+    let events = broker.on_new_market_data(&md);
+    assert_eq!(md_timestamps(&events), vec![140]);
+}
+
+#[test]
+fn amend_price_loses_queue_priority() {
+    let config = SimBrokerConfig::default().with_queue_model(QueueModel::DisplayedSize);
+    let (mut broker, sender) = new_broker(config);
+    let request = new_order_request("1", OrderType::LIMIT, Side::BUY, Some(99.0), 1.0, 100);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+    broker.on_new_market_data(&quote_with_size(99.0, 5.0, 100.0, 1.0, 100));
+    broker.on_new_market_data(&trade(99.0, 4.0, 110));
+
+    // moving price away and back puts order behind 5 more lots displayed at 99.0
+    let amend = amend_order_request("1", Some(98.0), None, 120);
+    sender.send(ExchangeRequest::AmendOrder(amend)).unwrap();
+    let events = broker.on_new_market_data(&quote_with_size(99.0, 1.0, 100.0, 1.0, 120));
+    let updates = order_updates(&events);
+    assert_eq!(updates.len(), 1);
+    assert_eq!(updates[0].execution_type, ExecutionType::AMENDMENT);
+    assert_eq!(updates[0].original_price, Some(98.0));
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, Event::ResponseAmendOrderAccepted(a) if a.price == Some(98.0))));
+
+    let amend = amend_order_request("1", Some(99.0), None, 130);
+    sender.send(ExchangeRequest::AmendOrder(amend)).unwrap();
+    broker.on_new_market_data(&quote_with_size(99.0, 5.0, 100.0, 1.0, 130));
+    let events = broker.on_new_market_data(&trade(99.0, 4.0, 140));
+    assert!(fills(&events).is_empty());
+    let events = broker.on_new_market_data(&trade(99.0, 2.0, 150));
+    assert_eq!(fills(&events).len(), 1);
+}
+
+#[test]
+fn amend_quantity_below_filled_rejected() {
+    let config = SimBrokerConfig::default().with_fill_model(FillModel::AvailableSize);
+    let (mut broker, sender) = new_broker(config);
+    let request = new_order_request("1", OrderType::LIMIT, Side::SELL, Some(100.0), 5.0, 100);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+    broker.on_new_market_data(&quote_with_size(100.0, 2.0, 101.0, 1.0, 100));
+
+    let amend = amend_order_request("1", None, Some(2.0), 110);
+    sender.send(ExchangeRequest::AmendOrder(amend)).unwrap();
+    let events = broker.on_new_market_data(&quote(99.0, 101.0, 110));
+    assert!(order_updates(&events).is_empty());
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, Event::ResponseAmendOrderRejected(_))));
+
+    let amend = amend_order_request("1", None, Some(3.0), 120);
+    sender.send(ExchangeRequest::AmendOrder(amend)).unwrap();
+    let events = broker.on_new_market_data(&trade(101.0, 10.0, 120));
+    let updates = order_updates(&events);
+    assert_eq!(updates[0].execution_type, ExecutionType::AMENDMENT);
+    assert_eq!(updates[1].order_status, OrderStatus::FILLED);
+    assert_eq!(updates[1].last_filled_qty, Some(1.0));
+    assert_eq!(updates[1].accumulated_filled_qty, Some(3.0));
+}
+
+#[test]
+fn cancel_all_cancels_open_orders_for_symbol() {
+    let (mut broker, sender) = new_broker(SimBrokerConfig::default());
+    for (client_order_id, price) in [("1", 90.0), ("2", 91.0)] {
+        let request = new_order_request(
+            client_order_id,
+            OrderType::LIMIT,
+            Side::BUY,
+            Some(price),
+            1.0,
+            100,
+        );
+        sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+    }
+    let mut other_symbol =
+        new_order_request("3", OrderType::LIMIT, Side::BUY, Some(90.0), 1.0, 100);
+    other_symbol.symbol = "other_symbol".to_string();
+    sender
+        .send(ExchangeRequest::NewOrder(other_symbol))
+        .unwrap();
+    broker.on_new_market_data(&quote(99.0, 101.0, 100));
+
+    let cancel_all = CancelAllRequest {
+        request_id: "cancel_all".to_string(),
+        exchange: EXCHANGE.to_string(),
+        symbol: Some(SYMBOL.to_string()),
+        creation_ts: 110,
+    };
+    sender.send(ExchangeRequest::CancelAll(cancel_all)).unwrap();
+    let events = broker.on_new_market_data(&quote(99.0, 101.0, 110));
+    let updates = order_updates(&events);
+    assert_eq!(updates.len(), 2);
+    assert!(updates
+        .iter()
+        .all(|u| u.order_status == OrderStatus::CANCELED && u.symbol == SYMBOL));
+
+    let cancel_all = CancelAllRequest {
+        request_id: "cancel_all_symbols".to_string(),
+        exchange: EXCHANGE.to_string(),
+        symbol: None,
+        creation_ts: 120,
+    };
+    sender.send(ExchangeRequest::CancelAll(cancel_all)).unwrap();
+    let events = broker.on_new_market_data(&quote(99.0, 101.0, 120));
+    let updates = order_updates(&events);
+    assert_eq!(updates.len(), 1);
+    assert_eq!(updates[0].client_order_id, Some("3".to_string()));
+    assert_eq!(updates[0].execution_type, ExecutionType::CANCELED);
+}
+
+#[test]
+fn cancel_by_client_order_id_before_order_accepted() {
+    let (mut broker, sender) = new_broker(SimBrokerConfig::new(false, Some(10), Some(5)));
+    let request = new_order_request("1", OrderType::LIMIT, Side::BUY, Some(90.0), 1.0, 100);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+    for client_order_id in ["1", "unknown"] {
+        let cancel = CancelOrderRequest {
+            request_id: format!("cancel_{}", client_order_id),
+            client_order_id: Some(client_order_id.to_string()),
+            exchange_order_id: None,
+            exchange: EXCHANGE.to_string(),
+            symbol: SYMBOL.to_string(),
+            creation_ts: 101,
+        };
+        sender.send(ExchangeRequest::CancelOrder(cancel)).unwrap();
+    }
+    broker.on_new_market_data(&quote(99.0, 101.0, 100));
+
+    let events = broker.on_new_market_data(&quote(99.0, 101.0, 150));
+    let updates = order_updates(&events);
+    assert_eq!(updates.len(), 2);
+    assert_eq!(updates[0].order_status, OrderStatus::NEW);
+    assert_eq!(updates[1].order_status, OrderStatus::CANCELED);
+    assert!(events.iter().any(|e| matches!(
+        e,
+        Event::ResponseCancelOrderAccepted(a) if a.client_order_id == "1" && a.exchange_order_id == "1"
+    )));
+    assert!(events.iter().any(|e| matches!(
+        e,
+        Event::ResponseCancelOrderRejected(r) if r.request_id == Some("cancel_unknown".to_string())
+    )));
+}
+
+#[test]
+fn marketable_order_walks_book_levels() {
+    let config = SimBrokerConfig::default().with_execution_model(ExecutionModel::OrderBookDepth);
+    let (mut broker, sender) = new_broker(config);
+    let request = new_order_request("1", OrderType::LIMIT, Side::BUY, Some(101.0), 5.0, 100);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+
+    // quotes and trades don't execute orders in depth mode
+    let events = broker.on_new_market_data(&quote(99.0, 100.0, 100));
+    assert!(fills(&events).is_empty());
+
+    let snapshot = book_update(
+        OrderBookUpdateType::SNAPSHOT,
+        1,
+        &[(99.0, 5.0)],
+        &[(100.0, 1.0), (100.5, 2.0), (101.0, 1.0), (102.0, 10.0)],
+        110,
+    );
+    let events = broker.on_new_market_data(&snapshot);
+    // updates are emitted in the order they happened
+    let accumulated: Vec<(OrderStatus, Option<f64>)> = order_updates(&events)
+        .into_iter()
+        .map(|u| (u.order_status, u.accumulated_filled_qty))
+        .collect();
+    assert_eq!(
+        accumulated,
+        vec![
+            (OrderStatus::PARTIALLY_FILLED, Some(1.0)),
+            (OrderStatus::PARTIALLY_FILLED, Some(3.0)),
+            (OrderStatus::PARTIALLY_FILLED, Some(4.0)),
+        ]
+    );
+    let fills = fills(&events);
+    let fill_prices: Vec<Option<f64>> = fills.iter().map(|f| f.last_filled_price).collect();
+    let fill_quantities: Vec<Option<f64>> = fills.iter().map(|f| f.last_filled_qty).collect();
+    assert_eq!(fill_prices, vec![Some(100.0), Some(100.5), Some(101.0)]);
+    assert_eq!(fill_quantities, vec![Some(1.0), Some(2.0), Some(1.0)]);
+    assert_eq!(fills[2].order_status, OrderStatus::PARTIALLY_FILLED);
+    assert_eq!(fills[2].average_price, Some(402.0 / 4.0));
+
+    // taken levels stay removed from broker book, remainder rests at 101.0 with empty queue
+    let delta = book_update(OrderBookUpdateType::DELTA, 2, &[(99.0, 4.0)], &[], 120);
+    assert!(order_updates(&broker.on_new_market_data(&delta)).is_empty());
+
+    // ask moves down to order price and crosses it
+    let delta = book_update(OrderBookUpdateType::DELTA, 3, &[], &[(101.0, 3.0)], 130);
+    let events = broker.on_new_market_data(&delta);
+    let last = &order_updates(&events)[0];
+    assert_eq!(last.last_filled_price, Some(101.0));
+    assert_eq!(last.last_filled_qty, Some(1.0));
+    assert_eq!(last.order_status, OrderStatus::FILLED);
+}
+
+#[test]
+fn resting_order_filled_as_level_ahead_is_depleted() {
+    let config = SimBrokerConfig::default().with_execution_model(ExecutionModel::OrderBookDepth);
+    let (mut broker, sender) = new_broker(config);
+    let request = new_order_request("1", OrderType::LIMIT, Side::SELL, Some(100.0), 2.0, 100);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+
+    // order joins behind 3 lots at 100.0, new size at the level joins behind the order
+    let snapshot = book_update(
+        OrderBookUpdateType::SNAPSHOT,
+        1,
+        &[(99.0, 5.0)],
+        &[(100.0, 3.0), (101.0, 2.0)],
+        100,
+    );
+    broker.on_new_market_data(&snapshot);
+    let delta = book_update(OrderBookUpdateType::DELTA, 2, &[], &[(100.0, 6.0)], 110);
+    assert!(fills(&broker.on_new_market_data(&delta)).is_empty());
+
+    let delta = book_update(OrderBookUpdateType::DELTA, 3, &[], &[(100.0, 4.0)], 120);
+    assert!(fills(&broker.on_new_market_data(&delta)).is_empty());
+
+    // 1 lot ahead is left, so 2 lots depleted fill 1 lot of the order
+    let delta = book_update(OrderBookUpdateType::DELTA, 4, &[], &[(100.0, 2.0)], 130);
+    let first = fills(&broker.on_new_market_data(&delta));
+    assert_eq!(first.len(), 1);
+    assert_eq!(first[0].last_filled_qty, Some(1.0));
+    assert_eq!(first[0].order_status, OrderStatus::PARTIALLY_FILLED);
+
+    // level is fully consumed
+    let delta = book_update(OrderBookUpdateType::DELTA, 5, &[], &[(100.0, 0.0)], 140);
+    let second = fills(&broker.on_new_market_data(&delta));
+    assert_eq!(second.len(), 1);
+    assert_eq!(second[0].last_filled_price, Some(100.0));
+    assert_eq!(second[0].last_filled_qty, Some(1.0));
+    assert_eq!(second[0].order_status, OrderStatus::FILLED);
+}
+
+#[test]
+fn crossed_resting_order_filled_after_queue_ahead() {
+    let config = SimBrokerConfig::default().with_execution_model(ExecutionModel::OrderBookDepth);
+    let (mut broker, sender) = new_broker(config);
+    let request = new_order_request("1", OrderType::LIMIT, Side::SELL, Some(100.0), 2.0, 100);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+
+    // order joins behind 3 lots at 100.0
+    let snapshot = book_update(
+        OrderBookUpdateType::SNAPSHOT,
+        1,
+        &[(99.0, 5.0)],
+        &[(100.0, 3.0), (101.0, 2.0)],
+        100,
+    );
+    broker.on_new_market_data(&snapshot);
+
+    // bid crosses order level: 3 lots ahead are matched first
+    let delta = book_update(OrderBookUpdateType::DELTA, 2, &[(100.0, 2.0)], &[], 110);
+    assert!(fills(&broker.on_new_market_data(&delta)).is_empty());
+
+    // crossed size is taken from broker book, new size at the level crosses again
+    let delta = book_update(OrderBookUpdateType::DELTA, 3, &[(100.0, 2.0)], &[], 120);
+    let fills = fills(&broker.on_new_market_data(&delta));
+    assert_eq!(fills.len(), 1);
+    assert_eq!(fills[0].last_filled_price, Some(100.0));
+    assert_eq!(fills[0].last_filled_qty, Some(1.0));
+    assert_eq!(fills[0].order_status, OrderStatus::PARTIALLY_FILLED);
+}
+
+#[test]
+fn immediate_orders_expire_while_book_is_not_synced() {
+    let config = SimBrokerConfig::default().with_execution_model(ExecutionModel::OrderBookDepth);
+    let (mut broker, sender) = new_broker(config);
+    let mut request = new_order_request("1", OrderType::LIMIT, Side::BUY, Some(101.0), 1.0, 100);
+    request.time_in_force = TimeInForce::IOC;
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+    let request = new_order_request("2", OrderType::LIMIT, Side::BUY, Some(101.0), 1.0, 100);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+
+    // delta without snapshot doesn't sync the book
+    let delta = book_update(OrderBookUpdateType::DELTA, 1, &[], &[(100.0, 1.0)], 100);
+    let expired: Vec<OrderUpdate> = order_updates(&broker.on_new_market_data(&delta))
+        .into_iter()
+        .filter(|u| u.execution_type == ExecutionType::EXPIRED)
+        .collect();
+    assert_eq!(expired.len(), 1);
+    assert_eq!(expired[0].client_order_id, Some("1".to_string()));
+    assert_eq!(broker.open_orders().len(), 1);
+}
+
+fn bar_exit_fills(intrabar_path: IntrabarPath) -> Vec<(Option<String>, Option<f64>)> {
+    let config =
+        SimBrokerConfig::default().with_execution_model(ExecutionModel::Bars(intrabar_path));
+    let (mut broker, sender) = new_broker(config);
+    let take_profit = new_order_request(
+        "take_profit",
+        OrderType::LIMIT,
+        Side::SELL,
+        Some(105.0),
+        1.0,
+        100,
+    );
+    let mut stop_loss = new_order_request("stop_loss", OrderType::STOP, Side::SELL, None, 1.0, 100);
+    stop_loss.trigger_price = Some(95.0);
+    sender.send(ExchangeRequest::NewOrder(take_profit)).unwrap();
+    sender.send(ExchangeRequest::NewOrder(stop_loss)).unwrap();
+
+    // trades don't execute orders in bars mode
+    let events = broker.on_new_market_data(&trade(110.0, 1.0, 100));
+    assert!(fills(&events).is_empty());
+
+    // bar closed down touches both exit prices
+    let events = broker.on_new_market_data(&bar(100.0, 106.0, 94.0, 96.0, 200));
+    fills(&events)
+        .into_iter()
+        .map(|f| (f.client_order_id, f.last_filled_price))
+        .collect()
+}
+
+#[test]
+fn bar_open_only_executes_at_open_price() {
+    assert!(bar_exit_fills(IntrabarPath::OpenOnly).is_empty());
+
+    let config = SimBrokerConfig::default()
+        .with_execution_model(ExecutionModel::Bars(IntrabarPath::OpenOnly));
+    let (mut broker, sender) = new_broker(config);
+    let request = new_order_request("1", OrderType::MARKET, Side::BUY, None, 1.0, 100);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+    let events = broker.on_new_market_data(&bar(101.0, 103.0, 99.0, 102.0, 200));
+    let fills = fills(&events);
+    assert_eq!(fills.len(), 1);
+    assert_eq!(fills[0].last_filled_price, Some(101.0));
+    assert_eq!(fills[0].exchange_timestamp, 200);
+}
+
+#[test]
+fn bar_high_low_touch_follows_bar_direction() {
+    assert_eq!(
+        bar_exit_fills(IntrabarPath::HighLowTouch),
+        vec![
+            (Some("take_profit".to_string()), Some(105.0)),
+            (Some("stop_loss".to_string()), Some(94.0)),
+        ]
+    );
+}
+
+#[test]
+fn bar_pessimistic_path_triggers_stop_first() {
+    assert_eq!(
+        bar_exit_fills(IntrabarPath::Pessimistic),
+        vec![
+            (Some("stop_loss".to_string()), Some(94.0)),
+            (Some("take_profit".to_string()), Some(105.0)),
+        ]
+    );
+}
+
+#[test]
+fn bar_volume_shared_by_fills_along_path() {
+    let config = SimBrokerConfig::default()
+        .with_execution_model(ExecutionModel::Bars(IntrabarPath::HighLowTouch))
+        .with_fill_model(FillModel::AvailableSize);
+    let (mut broker, sender) = new_broker(config);
+    let request = new_order_request("1", OrderType::MARKET, Side::BUY, None, 60.0, 100);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+    let request = new_order_request("2", OrderType::LIMIT, Side::BUY, Some(99.0), 80.0, 100);
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+
+    // 100 lots of bar volume: 60 are taken at open and 40 are left at low
+    let events = broker.on_new_market_data(&bar(100.0, 103.0, 98.0, 102.0, 200));
+    let filled: Vec<(Option<String>, Option<f64>)> = fills(&events)
+        .into_iter()
+        .map(|f| (f.client_order_id, f.last_filled_qty))
+        .collect();
+    assert_eq!(
+        filled,
+        vec![
+            (Some("1".to_string()), Some(60.0)),
+            (Some("2".to_string()), Some(40.0)),
+        ]
+    );
+
+    let events = broker.on_new_market_data(&bar(100.0, 103.0, 98.0, 102.0, 300));
+    let fills = fills(&events);
+    assert_eq!(fills.len(), 1);
+    assert_eq!(fills[0].last_filled_qty, Some(40.0));
+    assert_eq!(fills[0].order_status, OrderStatus::FILLED);
+}
+
+#[test]
+fn gtx_order_checked_against_bar_open() {
+    let config = SimBrokerConfig::default()
+        .with_execution_model(ExecutionModel::Bars(IntrabarPath::HighLowTouch));
+    let (mut broker, sender) = new_broker(config);
+    let mut request = new_order_request("1", OrderType::LIMIT, Side::BUY, Some(95.0), 1.0, 100);
+    request.time_in_force = TimeInForce::GTX;
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+    let mut request = new_order_request("2", OrderType::LIMIT, Side::BUY, Some(101.0), 1.0, 100);
+    request.time_in_force = TimeInForce::GTX;
+    sender.send(ExchangeRequest::NewOrder(request)).unwrap();
+
+    // order below the open rests, the one above it would take liquidity at open
+    let events = broker.on_new_market_data(&bar(100.0, 103.0, 96.0, 102.0, 200));
+    let statuses: Vec<(Option<String>, ExecutionType)> = order_updates(&events)
+        .into_iter()
+        .map(|u| (u.client_order_id, u.execution_type))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![
+            (Some("1".to_string()), ExecutionType::NEW),
+            (Some("2".to_string()), ExecutionType::NEW),
+            (Some("2".to_string()), ExecutionType::EXPIRED),
+        ]
+    );
+
+    let events = broker.on_new_market_data(&bar(97.0, 98.0, 90.0, 92.0, 300));
+    let fills = fills(&events);
+    assert_eq!(fills.len(), 1);
+    assert_eq!(fills[0].client_order_id, Some("1".to_string()));
+    assert_eq!(fills[0].last_filled_price, Some(95.0));
+}
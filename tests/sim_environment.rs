@@ -14,6 +14,7 @@ use geger::sim::environment::SimulatedTradingMarketDataProvider;
 use json_comments::StripComments;
 use log::info;
 use serde::{Deserialize, Serialize};
+#[allow(clippy::single_component_path_imports)]
 use serde_json;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
@@ -45,6 +46,7 @@ enum TestStrategyCollectedEvent {
     ExchangeRequest(ExchangeRequest),
 }
 
+#[allow(clippy::let_and_return)]
 fn get_expected_events_from_fixture() -> Vec<TestStrategyCollectedEvent> {
     let cwd = env::current_dir().unwrap();
     let fixture_path = cwd.join(EXPECTED_COLLECTED_EVENTS_PATH);
@@ -69,6 +71,7 @@ impl TestEventSequenceMDProvider {
         }
     }
 
+    #[allow(clippy::let_and_return)]
     fn get_md_events_from_fixture(fixture_path: &str) -> Vec<MarketDataEvent> {
         let cwd = env::current_dir().unwrap();
         let fixture_path = cwd.join(fixture_path);
@@ -243,8 +246,8 @@ impl<M: Message, MS: MessageSender<M>> Actor<M, MS> for TestStrategy {
                             let clio = self.open_order.as_ref().unwrap().clone();
                             let cancel_request = CancelOrderRequest {
                                 request_id: self.get_request_id(),
-                                client_order_id: clio.clone(),
-                                exchange_order_id: clio,
+                                client_order_id: Some(clio.clone()),
+                                exchange_order_id: Some(clio),
                                 exchange: TRADE_EXCHANGE.to_string(),
                                 symbol: TRADE_SYMBOL.to_string(),
                                 creation_ts: event.timestamp(),
@@ -274,6 +277,7 @@ impl<M: Message, MS: MessageSender<M>> Actor<M, MS> for TestStrategy {
 }
 
 #[test]
+#[allow(clippy::infallible_destructuring_match)]
 fn check_event_sequence_single_exchange_symbol() {
     let expected_collected_events = get_expected_events_from_fixture();
 
@@ -324,6 +328,11 @@ fn check_event_sequence_single_exchange_symbol() {
 }
 
 #[test]
+#[allow(
+    clippy::infallible_destructuring_match,
+    clippy::needless_range_loop,
+    clippy::single_match
+)]
 fn check_event_sequence_multiple_exchanges_symbols() {
     let expected_collected_events = get_expected_events_from_fixture();
     let expected_md_events =
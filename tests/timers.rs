@@ -0,0 +1,228 @@
+use geger::core::actions_context::ActionsContext;
+use geger::core::engine::{Engine, SimRunResult};
+use geger::core::event_loop::{Actor, EventProvider, StopHandle};
+use geger::core::events::Event;
+use geger::core::gateway_router::NewOrderRequest;
+use geger::core::market_data::{MarketDataEvent, Quote};
+use geger::core::message_bus::{CrossbeamMessageSender, LoggerMessageHandler, SimpleMessage};
+use geger::core::timer::TimerId;
+use geger::core::types::{OrderType, Side, TimeInForce, Timestamp};
+use geger::sim::broker::SimBrokerConfig;
+use geger::sim::environment::SimulatedTradingMarketDataProvider;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+const EXCHANGE: &str = "test_exchange";
+const SYMBOL: &str = "test_symbol";
+
+struct VecMDProvider {
+    events: VecDeque<MarketDataEvent>,
+}
+
+impl SimulatedTradingMarketDataProvider for VecMDProvider {
+    fn next_event(&mut self) -> Option<MarketDataEvent> {
+        self.events.pop_front()
+    }
+}
+
+fn md_provider(timestamps: &[Timestamp]) -> VecMDProvider {
+    let events = timestamps
+        .iter()
+        .map(|&ts| {
+            MarketDataEvent::NewQuote(Quote {
+                event_id: None,
+                symbol: SYMBOL.to_string(),
+                exchange: EXCHANGE.to_string(),
+                bid: 99.0,
+                ask: 100.0,
+                bid_size: None,
+                ask_size: None,
+                exchange_timestamp: ts,
+                received_timestamp: ts,
+            })
+        })
+        .collect();
+    VecMDProvider { events }
+}
+
+#[derive(Debug, Default)]
+struct TimerStrategy {
+    recurring_timer: Option<TimerId>,
+    one_shot_timer: Option<TimerId>,
+    fired_timers: Vec<(TimerId, Timestamp)>,
+    event_timestamps: Vec<Timestamp>,
+    clock_timestamps: Vec<Timestamp>,
+    order_accepted_ts: Option<Timestamp>,
+}
+
+impl Actor<SimpleMessage, CrossbeamMessageSender<SimpleMessage>> for TimerStrategy {
+    fn on_event(
+        &mut self,
+        event: &Event,
+        actions_context: &mut ActionsContext<SimpleMessage, CrossbeamMessageSender<SimpleMessage>>,
+    ) {
+        self.event_timestamps.push(event.timestamp());
+        self.clock_timestamps.push(actions_context.now());
+        match event {
+            Event::NewQuote(quote) if self.recurring_timer.is_none() => {
+                let ts = quote.received_timestamp;
+                self.recurring_timer = Some(
+                    actions_context
+                        .schedule_recurring_timer(ts + 20, 100)
+                        .unwrap(),
+                );
+                self.one_shot_timer = Some(actions_context.schedule_timer(ts + 50).unwrap());
+                let cancelled = actions_context.schedule_timer(ts + 60).unwrap();
+                actions_context.cancel_timer(cancelled).unwrap();
+                let request = NewOrderRequest {
+                    request_id: "1".to_string(),
+                    client_order_id: "1".to_string(),
+                    exchange: EXCHANGE.to_string(),
+                    r#type: OrderType::LIMIT,
+                    time_in_force: TimeInForce::GTC,
+                    price: Some(90.0),
+                    trigger_price: None,
+                    symbol: SYMBOL.to_string(),
+                    quantity: 1.0,
+                    side: Side::BUY,
+                    // stamped by actions context from sim clock
+                    creation_ts: 0,
+                };
+                actions_context.send_order(request).unwrap();
+            }
+            Event::ResponseNewOrderAccepted(e) => self.order_accepted_ts = Some(e.timestamp),
+            Event::Timer(timer) => self.fired_timers.push((timer.timer_id, timer.timestamp)),
+            _ => {}
+        }
+    }
+}
+
+fn run_sim(strategy: Arc<Mutex<TimerStrategy>>) -> SimRunResult {
+    let mut engine: Engine<
+        TimerStrategy,
+        SimpleMessage,
+        CrossbeamMessageSender<SimpleMessage>,
+        LoggerMessageHandler,
+    > = Engine::new();
+    engine.add_exchange(EXCHANGE.to_string());
+    engine.add_actor(strategy);
+    let mut sim_broker_configs = HashMap::new();
+    sim_broker_configs.insert(
+        EXCHANGE.to_string(),
+        SimBrokerConfig::new(false, Some(30), Some(5)),
+    );
+
+    engine
+        .run_with_sim_environment(
+            md_provider(&[100, 200, 300, 400]),
+            None,
+            sim_broker_configs,
+            false,
+            StopHandle::new(),
+        )
+        .unwrap()
+}
+
+#[test]
+fn timers_fire_at_simulated_timestamps() {
+    let strategy = Arc::new(Mutex::new(TimerStrategy::default()));
+    let result = run_sim(strategy.clone());
+    assert!(result.errors.is_empty());
+    assert_eq!(result.event_counts.timers, 4);
+
+    let strategy = strategy.lock().unwrap();
+    let recurring = strategy.recurring_timer.unwrap();
+    let one_shot = strategy.one_shot_timer.unwrap();
+    assert_eq!(
+        strategy.fired_timers,
+        vec![
+            (recurring, 150),
+            (one_shot, 180),
+            (recurring, 250),
+            (recurring, 350)
+        ]
+    );
+    // first quote is received at 130, order is acked at 160 and accepted is received at 195
+    assert_eq!(strategy.order_accepted_ts, Some(195));
+    assert!(strategy.event_timestamps.windows(2).all(|w| w[0] <= w[1]));
+}
+
+#[test]
+fn clock_reports_simulated_time() {
+    let strategy = Arc::new(Mutex::new(TimerStrategy::default()));
+    let result = run_sim(strategy.clone());
+    assert!(result.errors.is_empty());
+
+    let strategy = strategy.lock().unwrap();
+    assert_eq!(strategy.clock_timestamps, strategy.event_timestamps);
+    // order is sent at 130 although request creation_ts is not set by strategy
+    assert_eq!(strategy.order_accepted_ts, Some(195));
+}
+
+struct LiveQuotes {
+    quotes: VecDeque<Event>,
+}
+
+impl EventProvider for LiveQuotes {
+    fn next_event(&mut self) -> Option<Event> {
+        self.quotes.pop_front()
+    }
+}
+
+#[derive(Debug, Default)]
+struct LiveTimerStrategy {
+    timer: Option<(TimerId, Timestamp)>,
+    events: Vec<Event>,
+}
+
+impl Actor<SimpleMessage, CrossbeamMessageSender<SimpleMessage>> for LiveTimerStrategy {
+    fn on_event(
+        &mut self,
+        event: &Event,
+        actions_context: &mut ActionsContext<SimpleMessage, CrossbeamMessageSender<SimpleMessage>>,
+    ) {
+        self.events.push(event.clone());
+        if self.timer.is_none() {
+            let now = actions_context.now();
+            self.timer = Some((actions_context.schedule_timer(now).unwrap(), now));
+        }
+    }
+}
+
+#[test]
+fn timers_fire_by_wall_clock_with_external_event_provider() {
+    let quotes = md_provider(&[100, 200, 300])
+        .events
+        .into_iter()
+        .map(|md| match md {
+            MarketDataEvent::NewQuote(q) => Event::NewQuote(q),
+            other => unreachable!("{:?}", other),
+        })
+        .collect();
+    let strategy = Arc::new(Mutex::new(LiveTimerStrategy::default()));
+    let mut engine: Engine<
+        LiveTimerStrategy,
+        SimpleMessage,
+        CrossbeamMessageSender<SimpleMessage>,
+        LoggerMessageHandler,
+    > = Engine::new();
+    engine.add_actor(strategy.clone());
+    let execution_info = engine
+        .start_with_event_provider(LiveQuotes { quotes }, false)
+        .unwrap();
+    for th in execution_info.threads {
+        th.unwrap().join().unwrap()
+    }
+
+    // timer due on scheduling is delivered before the next event of provider
+    let strategy = strategy.lock().unwrap();
+    let (timer_id, scheduled_ts) = strategy.timer.unwrap();
+    assert_eq!(strategy.events.len(), 4);
+    match &strategy.events[1] {
+        Event::Timer(timer) => {
+            assert_eq!(timer.timer_id, timer_id);
+            assert_eq!(timer.timestamp, scheduled_ts);
+        }
+        other => panic!("unexpected event: {:?}", other),
+    }
+}